@@ -0,0 +1,322 @@
+use std::{ mem, ptr, sync::{ atomic::{ AtomicPtr, Ordering }, Arc } };
+
+use crate::{ MergeableHeap, Owner };
+
+// 二项堆（第2版第19章，第3版思考题19-2）
+// 由一组二项树组成，根链表按度数严格递增，用sibling单向连接
+// 孩子用“左孩子右兄弟”表示，孩子链表按度数递减
+// n个节点的二项堆对应n的二进制表示，所以最多有floor(lgn) + 1棵树
+// 合并两个堆就像二进制加法，度数相同的两棵树合成一棵，向高位进位
+
+// 节点和它的句柄共享的槽，堆和句柄可能在不同的线程上，所以是原子的
+// 只有持有&mut堆的线程才会写它或者解引用读到的指针，堆换线程时已经同步过，Relaxed就够了
+type Slot<K> = Arc<AtomicPtr<Node<K>>>;
+
+struct Node<K> {
+    key: K,
+    degree: usize,
+    parent: *mut Node<K>,
+    child: *mut Node<K>,
+    sibling: *mut Node<K>,
+    slot: Slot<K>,
+}
+
+pub struct BinomialHeap<K> {
+    head: *mut Node<K>,
+    n: usize,
+    owner: Arc<Owner>,
+}
+
+// SAFETY: 节点都是堆自己Box出来的，只能通过堆里的裸指针到达，堆独占它们，随堆一起移动
+// 句柄和堆共享的只有slot和owner，一个是Arc<AtomicPtr>，一个是Arc<Owner>（里面是Mutex），
+// 引用计数和读写都是线程安全的；句柄里的指针只在持有&mut堆并确认句柄属于这个堆以后才解引用
+// 所以键能在线程之间移动时，堆也能
+unsafe impl<K: Send> Send for BinomialHeap<K> {}
+
+pub struct BinomialHandle<K> {
+    slot: Slot<K>,
+    owner: Arc<Owner>,
+}
+
+impl<K> Clone for BinomialHandle<K> {
+    fn clone(&self) -> Self {
+        BinomialHandle { slot: self.slot.clone(), owner: self.owner.clone() }
+    }
+}
+
+impl<K: Ord> BinomialHeap<K> {
+    pub fn new() -> BinomialHeap<K> {
+        BinomialHeap { head: ptr::null_mut(), n: 0, owner: Owner::new() }
+    }
+
+    fn node_of(&self, handle: &BinomialHandle<K>) -> Result<*mut Node<K>, &'static str> {
+        let x = handle.slot.load(Ordering::Relaxed);
+        if x.is_null() {
+            Err("Handle is no longer valid")
+        } else if !Owner::owns(&self.owner, &handle.owner) {
+            Err("Handle does not belong to this heap")
+        } else {
+            Ok(x)
+        }
+    }
+
+    // 返回最小的根和它在根链表中的前驱
+    unsafe fn minimum_root(&self) -> (*mut Node<K>, *mut Node<K>) {
+        let mut prev = ptr::null_mut();
+        let mut min = self.head;
+        let mut prev_x = self.head;
+        let mut x = (*self.head).sibling;
+        while !x.is_null() {
+            if (*x).key < (*min).key {
+                min = x;
+                prev = prev_x;
+            }
+            prev_x = x;
+            x = (*x).sibling;
+        }
+        (min, prev)
+    }
+
+    // BINOMIAL-HEAP-UNION：先按度数归并两个根链表，再把相邻的同度数树两两合并
+    unsafe fn union_roots(&mut self, other_head: *mut Node<K>) {
+        self.head = merge_roots(self.head, other_head);
+        if self.head.is_null() {
+            return;
+        }
+        let mut prev_x: *mut Node<K> = ptr::null_mut();
+        let mut x = self.head;
+        let mut next_x = (*x).sibling;
+        while !next_x.is_null() {
+            if
+                (*x).degree != (*next_x).degree ||
+                (!(*next_x).sibling.is_null() && (*(*next_x).sibling).degree == (*x).degree)
+            {
+                // 度数不同，或者后面有三棵同度数的树，先跳过一棵
+                prev_x = x;
+                x = next_x;
+            } else if (*x).key <= (*next_x).key {
+                (*x).sibling = (*next_x).sibling;
+                link(next_x, x);
+            } else {
+                if prev_x.is_null() {
+                    self.head = next_x;
+                } else {
+                    (*prev_x).sibling = next_x;
+                }
+                link(x, next_x);
+                x = next_x;
+            }
+            next_x = (*x).sibling;
+        }
+    }
+
+    // 把根x（前驱为prev）从根链表中移除，它的孩子逆序后作为一个新堆并回来
+    unsafe fn remove_root(&mut self, x: *mut Node<K>, prev: *mut Node<K>) {
+        if prev.is_null() {
+            self.head = (*x).sibling;
+        } else {
+            (*prev).sibling = (*x).sibling;
+        }
+
+        let mut reversed = ptr::null_mut();
+        let mut child = (*x).child;
+        while !child.is_null() {
+            let next = (*child).sibling;
+            (*child).parent = ptr::null_mut();
+            (*child).sibling = reversed;
+            reversed = child;
+            child = next;
+        }
+        (*x).child = ptr::null_mut();
+        self.union_roots(reversed);
+        self.n -= 1;
+    }
+
+    // 把x上浮，force为真时无视关键字一直浮到根（相当于减值到负无穷）
+    // 交换的是关键字，所以句柄也要跟着交换
+    unsafe fn bubble_up(&mut self, mut x: *mut Node<K>, force: bool) -> *mut Node<K> {
+        let mut y = (*x).parent;
+        while !y.is_null() && (force || (*x).key < (*y).key) {
+            mem::swap(&mut (*x).key, &mut (*y).key);
+            mem::swap(&mut (*x).slot, &mut (*y).slot);
+            (*x).slot.store(x, Ordering::Relaxed);
+            (*y).slot.store(y, Ordering::Relaxed);
+            x = y;
+            y = (*x).parent;
+        }
+        x
+    }
+}
+
+impl<K: Ord> Default for BinomialHeap<K> {
+    fn default() -> Self {
+        BinomialHeap::new()
+    }
+}
+
+impl<K: Ord> MergeableHeap for BinomialHeap<K> {
+    type Key = K;
+    type Handle = BinomialHandle<K>;
+
+    fn len(&self) -> usize {
+        self.n
+    }
+
+    fn insert(&mut self, key: K) -> BinomialHandle<K> {
+        let slot = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let x = Box::into_raw(
+            Box::new(Node {
+                key,
+                degree: 0,
+                parent: ptr::null_mut(),
+                child: ptr::null_mut(),
+                sibling: ptr::null_mut(),
+                slot: slot.clone(),
+            })
+        );
+        slot.store(x, Ordering::Relaxed);
+        unsafe {
+            self.union_roots(x);
+        }
+        self.n += 1;
+        BinomialHandle { slot, owner: self.owner.clone() }
+    }
+
+    fn minimum(&self) -> Option<&K> {
+        if self.head.is_null() {
+            return None;
+        }
+        unsafe {
+            let (min, _) = self.minimum_root();
+            Some(&(*min).key)
+        }
+    }
+
+    fn extract_min(&mut self) -> Option<K> {
+        if self.head.is_null() {
+            return None;
+        }
+        unsafe {
+            let (x, prev) = self.minimum_root();
+            self.remove_root(x, prev);
+            let x = Box::from_raw(x);
+            x.slot.store(ptr::null_mut(), Ordering::Relaxed);
+            Some(x.key)
+        }
+    }
+
+    fn union(&mut self, mut other: Self) {
+        Owner::forward(&other.owner, &self.owner);
+        unsafe {
+            self.union_roots(other.head);
+        }
+        self.n += other.n;
+        other.head = ptr::null_mut();
+        other.n = 0;
+    }
+
+    fn decrease_key(&mut self, handle: &BinomialHandle<K>, key: K) -> Result<(), &'static str> {
+        let x = self.node_of(handle)?;
+        unsafe {
+            if key > (*x).key {
+                return Err("New key is greater than current key");
+            }
+            (*x).key = key;
+            self.bubble_up(x, false);
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, handle: &BinomialHandle<K>) -> Result<K, &'static str> {
+        let x = self.node_of(handle)?;
+        unsafe {
+            let root = self.bubble_up(x, true);
+            let mut prev = ptr::null_mut();
+            let mut cur = self.head;
+            while cur != root {
+                prev = cur;
+                cur = (*cur).sibling;
+            }
+            self.remove_root(root, prev);
+            let root = Box::from_raw(root);
+            root.slot.store(ptr::null_mut(), Ordering::Relaxed);
+            Ok(root.key)
+        }
+    }
+}
+
+impl<K> Drop for BinomialHeap<K> {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        if !self.head.is_null() {
+            stack.push(self.head);
+        }
+        while let Some(mut x) = stack.pop() {
+            while !x.is_null() {
+                unsafe {
+                    let node = Box::from_raw(x);
+                    node.slot.store(ptr::null_mut(), Ordering::Relaxed);
+                    if !node.child.is_null() {
+                        stack.push(node.child);
+                    }
+                    x = node.sibling;
+                }
+            }
+        }
+    }
+}
+
+// y成为z的最左孩子，两棵B(k-1)合成一棵B(k)
+unsafe fn link<K>(y: *mut Node<K>, z: *mut Node<K>) {
+    (*y).parent = z;
+    (*y).sibling = (*z).child;
+    (*z).child = y;
+    (*z).degree += 1;
+}
+
+// 按度数归并两个根链表，和归并排序的merge一样
+unsafe fn merge_roots<K>(mut a: *mut Node<K>, mut b: *mut Node<K>) -> *mut Node<K> {
+    let mut head = ptr::null_mut();
+    let mut tail: *mut Node<K> = ptr::null_mut();
+    while !a.is_null() || !b.is_null() {
+        let x;
+        if b.is_null() || (!a.is_null() && (*a).degree <= (*b).degree) {
+            x = a;
+            a = (*a).sibling;
+        } else {
+            x = b;
+            b = (*b).sibling;
+        }
+        if tail.is_null() {
+            head = x;
+        } else {
+            (*tail).sibling = x;
+        }
+        tail = x;
+    }
+    head
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_follows_binary_representation() {
+        let mut heap = BinomialHeap::new();
+        for key in 0..13 {
+            heap.insert(key);
+        }
+        // 13 = 0b1101，根的度数依次为0、2、3
+        let mut degrees = vec![];
+        let mut x = heap.head;
+        while !x.is_null() {
+            unsafe {
+                degrees.push((*x).degree);
+                x = (*x).sibling;
+            }
+        }
+        assert_eq!(degrees, vec![0, 2, 3]);
+        assert_eq!(heap.minimum(), Some(&0));
+    }
+}
@@ -0,0 +1,427 @@
+use std::{ ptr, sync::{ atomic::{ AtomicPtr, Ordering }, Arc } };
+
+use crate::{ MergeableHeap, Owner };
+
+// 斐波那契堆
+// 根链表和每个节点的孩子链表都是环形双向链表，min指向根链表中关键字最小的节点
+// 插入和合并只是把链表接起来，整理（consolidate）推迟到extract_min时才做
+// mark表示节点成为孩子之后是否失去过孩子，失去第二个孩子时要被切下来（级联切断），
+// 这保证了度为k的节点至少有F(k+2)个后代，所以最大度数是O(lgn)
+
+// 节点和它的句柄共享的槽，堆和句柄可能在不同的线程上，所以是原子的
+// 只有持有&mut堆的线程才会写它或者解引用读到的指针，堆换线程时已经同步过，Relaxed就够了
+type Slot<K> = Arc<AtomicPtr<Node<K>>>;
+
+struct Node<K> {
+    key: K,
+    degree: usize,
+    mark: bool,
+    parent: *mut Node<K>,
+    child: *mut Node<K>,
+    left: *mut Node<K>,
+    right: *mut Node<K>,
+    slot: Slot<K>, // 句柄指向这里，节点释放时清空
+}
+
+pub struct FibonacciHeap<K> {
+    min: *mut Node<K>,
+    n: usize,
+    owner: Arc<Owner>,
+}
+
+// SAFETY: 节点都是堆自己Box出来的，只能通过堆里的裸指针到达，堆独占它们，随堆一起移动
+// 句柄和堆共享的只有slot和owner，一个是Arc<AtomicPtr>，一个是Arc<Owner>（里面是Mutex），
+// 引用计数和读写都是线程安全的；句柄里的指针只在持有&mut堆并确认句柄属于这个堆以后才解引用
+// 所以键能在线程之间移动时，堆也能
+unsafe impl<K: Send> Send for FibonacciHeap<K> {}
+
+pub struct FibonacciHandle<K> {
+    slot: Slot<K>,
+    owner: Arc<Owner>,
+}
+
+impl<K> Clone for FibonacciHandle<K> {
+    fn clone(&self) -> Self {
+        FibonacciHandle { slot: self.slot.clone(), owner: self.owner.clone() }
+    }
+}
+
+impl<K: Ord> FibonacciHeap<K> {
+    pub fn new() -> FibonacciHeap<K> {
+        FibonacciHeap { min: ptr::null_mut(), n: 0, owner: Owner::new() }
+    }
+
+    fn node_of(&self, handle: &FibonacciHandle<K>) -> Result<*mut Node<K>, &'static str> {
+        let x = handle.slot.load(Ordering::Relaxed);
+        if x.is_null() {
+            Err("Handle is no longer valid")
+        } else if !Owner::owns(&self.owner, &handle.owner) {
+            Err("Handle does not belong to this heap")
+        } else {
+            Ok(x)
+        }
+    }
+
+    // 把x加入根链表（min的左边），不更新min
+    unsafe fn add_root(&mut self, x: *mut Node<K>) {
+        (*x).parent = ptr::null_mut();
+        if self.min.is_null() {
+            (*x).left = x;
+            (*x).right = x;
+            self.min = x;
+        } else {
+            splice(self.min, x);
+        }
+    }
+
+    // 把y挂到x下面，y原本在根链表中
+    unsafe fn link(&mut self, y: *mut Node<K>, x: *mut Node<K>) {
+        unlink(y);
+        (*y).parent = x;
+        if (*x).child.is_null() {
+            (*y).left = y;
+            (*y).right = y;
+            (*x).child = y;
+        } else {
+            splice((*x).child, y);
+        }
+        (*x).degree += 1;
+        (*y).mark = false;
+    }
+
+    // 合并根链表中度数相同的树，直到每个度数只剩一棵，再重新找出min
+    unsafe fn consolidate(&mut self) {
+        let mut roots = vec![];
+        let mut w = self.min;
+        loop {
+            roots.push(w);
+            w = (*w).right;
+            if w == self.min {
+                break;
+            }
+        }
+
+        // 度数的上界是log_φ(n)，φ > 1.5，用lg(n) * 1.5 + 2 足够了
+        let max_degree = ((usize::BITS - self.n.leading_zeros()) as usize) * 3 / 2 + 2;
+        let mut a: Vec<*mut Node<K>> = vec![ptr::null_mut(); max_degree];
+        for w in roots {
+            let mut x = w;
+            let mut d = (*x).degree;
+            while !a[d].is_null() {
+                let mut y = a[d];
+                if (*y).key < (*x).key {
+                    (x, y) = (y, x);
+                }
+                self.link(y, x);
+                a[d] = ptr::null_mut();
+                d += 1;
+            }
+            a[d] = x;
+        }
+
+        self.min = ptr::null_mut();
+        for x in a {
+            if !x.is_null() {
+                self.add_root(x);
+                if (*x).key < (*self.min).key {
+                    self.min = x;
+                }
+            }
+        }
+    }
+
+    // 把x从父节点y的孩子链表中切下，放进根链表
+    unsafe fn cut(&mut self, x: *mut Node<K>, y: *mut Node<K>) {
+        if (*x).right == x {
+            (*y).child = ptr::null_mut();
+        } else {
+            if (*y).child == x {
+                (*y).child = (*x).right;
+            }
+            unlink(x);
+        }
+        (*y).degree -= 1;
+        self.add_root(x);
+        (*x).mark = false;
+    }
+
+    // 书中是尾递归，这里写成循环
+    unsafe fn cascading_cut(&mut self, mut y: *mut Node<K>) {
+        loop {
+            let z = (*y).parent;
+            if z.is_null() {
+                break;
+            }
+            if !(*y).mark {
+                (*y).mark = true;
+                break;
+            }
+            self.cut(y, z);
+            y = z;
+        }
+    }
+
+    // 把min从根链表中移除，孩子全部升为根，返回被移除的节点
+    unsafe fn remove_min(&mut self) -> *mut Node<K> {
+        let z = self.min;
+        let child = (*z).child;
+        if !child.is_null() {
+            let mut x = child;
+            loop {
+                (*x).parent = ptr::null_mut();
+                x = (*x).right;
+                if x == child {
+                    break;
+                }
+            }
+            // 孩子链表整个接到根链表里
+            concat(z, child);
+            (*z).child = ptr::null_mut();
+        }
+
+        if (*z).right == z {
+            self.min = ptr::null_mut();
+        } else {
+            self.min = (*z).right;
+            unlink(z);
+            self.consolidate();
+        }
+        self.n -= 1;
+        z
+    }
+}
+
+impl<K: Ord> Default for FibonacciHeap<K> {
+    fn default() -> Self {
+        FibonacciHeap::new()
+    }
+}
+
+impl<K: Ord> MergeableHeap for FibonacciHeap<K> {
+    type Key = K;
+    type Handle = FibonacciHandle<K>;
+
+    fn len(&self) -> usize {
+        self.n
+    }
+
+    fn insert(&mut self, key: K) -> FibonacciHandle<K> {
+        let slot = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let x = Box::into_raw(
+            Box::new(Node {
+                key,
+                degree: 0,
+                mark: false,
+                parent: ptr::null_mut(),
+                child: ptr::null_mut(),
+                left: ptr::null_mut(),
+                right: ptr::null_mut(),
+                slot: slot.clone(),
+            })
+        );
+        slot.store(x, Ordering::Relaxed);
+        unsafe {
+            self.add_root(x);
+            if (*x).key < (*self.min).key {
+                self.min = x;
+            }
+        }
+        self.n += 1;
+        FibonacciHandle { slot, owner: self.owner.clone() }
+    }
+
+    fn minimum(&self) -> Option<&K> {
+        unsafe { self.min.as_ref().map(|node| &node.key) }
+    }
+
+    fn extract_min(&mut self) -> Option<K> {
+        if self.min.is_null() {
+            return None;
+        }
+        unsafe {
+            let z = Box::from_raw(self.remove_min());
+            z.slot.store(ptr::null_mut(), Ordering::Relaxed);
+            Some(z.key)
+        }
+    }
+
+    fn union(&mut self, mut other: Self) {
+        Owner::forward(&other.owner, &self.owner);
+        if other.min.is_null() {
+            return;
+        }
+        unsafe {
+            if self.min.is_null() {
+                self.min = other.min;
+            } else {
+                concat(self.min, other.min);
+                if (*other.min).key < (*self.min).key {
+                    self.min = other.min;
+                }
+            }
+        }
+        self.n += other.n;
+        // other不再拥有节点，它的drop什么都不用做
+        other.min = ptr::null_mut();
+        other.n = 0;
+    }
+
+    fn decrease_key(&mut self, handle: &FibonacciHandle<K>, key: K) -> Result<(), &'static str> {
+        let x = self.node_of(handle)?;
+        unsafe {
+            if key > (*x).key {
+                return Err("New key is greater than current key");
+            }
+            (*x).key = key;
+            let y = (*x).parent;
+            if !y.is_null() && (*x).key < (*y).key {
+                self.cut(x, y);
+                self.cascading_cut(y);
+            }
+            if (*x).key < (*self.min).key {
+                self.min = x;
+            }
+        }
+        Ok(())
+    }
+
+    // 书中是先减值到负无穷再抽取，这里没有负无穷，直接把x当成最小值切到根链表
+    fn delete(&mut self, handle: &FibonacciHandle<K>) -> Result<K, &'static str> {
+        let x = self.node_of(handle)?;
+        unsafe {
+            let y = (*x).parent;
+            if !y.is_null() {
+                self.cut(x, y);
+                self.cascading_cut(y);
+            }
+            self.min = x;
+            let z = Box::from_raw(self.remove_min());
+            z.slot.store(ptr::null_mut(), Ordering::Relaxed);
+            Ok(z.key)
+        }
+    }
+}
+
+// 逐个释放节点，用显式栈代替递归，避免深树爆栈
+impl<K> Drop for FibonacciHeap<K> {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        if !self.min.is_null() {
+            stack.push(self.min);
+        }
+        while let Some(list) = stack.pop() {
+            unsafe {
+                let mut x = list;
+                loop {
+                    let next = (*x).right;
+                    let node = Box::from_raw(x);
+                    node.slot.store(ptr::null_mut(), Ordering::Relaxed);
+                    if !node.child.is_null() {
+                        stack.push(node.child);
+                    }
+                    x = next;
+                    if x == list {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 把单个节点x插到a的左边
+unsafe fn splice<K>(a: *mut Node<K>, x: *mut Node<K>) {
+    (*x).right = a;
+    (*x).left = (*a).left;
+    (*(*a).left).right = x;
+    (*a).left = x;
+}
+
+// 把x从所在的环形链表中摘除，x自身的左右指针不变
+unsafe fn unlink<K>(x: *mut Node<K>) {
+    (*(*x).left).right = (*x).right;
+    (*(*x).right).left = (*x).left;
+}
+
+// 连接两个环形链表
+unsafe fn concat<K>(a: *mut Node<K>, b: *mut Node<K>) {
+    let a_left = (*a).left;
+    let b_left = (*b).left;
+    (*a_left).right = b;
+    (*b).left = a_left;
+    (*b_left).right = a;
+    (*a).left = b_left;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // x的所有孩子
+    unsafe fn children<K>(x: *mut Node<K>) -> Vec<*mut Node<K>> {
+        let mut result = vec![];
+        let first = (*x).child;
+        if first.is_null() {
+            return result;
+        }
+        let mut y = first;
+        loop {
+            result.push(y);
+            y = (*y).right;
+            if y == first {
+                break;
+            }
+        }
+        result
+    }
+
+    unsafe fn child_of_degree<K>(x: *mut Node<K>, degree: usize) -> *mut Node<K> {
+        *children(x)
+            .iter()
+            .find(|&&y| (*y).degree == degree)
+            .unwrap()
+    }
+
+    #[test]
+    fn it_cascading_cut() {
+        // 插入后抽取一次，剩下的16个节点整理成一棵B4
+        let mut heap = FibonacciHeap::new();
+        let handles: Vec<_> = (0..17).map(|k| heap.insert(k)).collect();
+        assert_eq!(heap.extract_min(), Some(0));
+        unsafe {
+            let root = heap.min;
+            assert_eq!((*root).degree, 4);
+            // root -> g（B3）-> y（B2）-> y的两个孩子
+            let g = child_of_degree(root, 3);
+            let y = child_of_degree(g, 2);
+            let other = child_of_degree(g, 0);
+            let (c1, c2) = (child_of_degree(y, 0), child_of_degree(y, 1));
+            let handle = |x: *mut Node<i32>| &handles[(*x).key as usize];
+
+            // g失去一个孩子，被标记
+            heap.decrease_key(handle(other), -1).unwrap();
+            assert!((*g).mark);
+            // y失去第一个孩子，被标记，还留在g下面
+            heap.decrease_key(handle(c1), -2).unwrap();
+            assert!((*y).mark);
+            assert_eq!((*y).parent, g);
+
+            // y失去第二个孩子：y被切下来，g已经标记过，也跟着被切下来，一直级联到根
+            heap.decrease_key(handle(c2), -3).unwrap();
+            assert!((*y).parent.is_null() && !(*y).mark);
+            assert!((*g).parent.is_null() && !(*g).mark);
+            assert_eq!((*root).degree, 3);
+            assert_eq!((*g).degree, 1);
+            assert_eq!((*y).degree, 0);
+            assert_eq!(heap.len(), 16);
+        }
+
+        let mut result = vec![];
+        while let Some(key) = heap.extract_min() {
+            result.push(key);
+        }
+        assert_eq!(result.len(), 16);
+        assert_eq!(&result[..3], &[-3, -2, -1]);
+        assert!(result.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -0,0 +1,258 @@
+// 可合并堆（《算法导论》第19章）
+// 除了优先队列的插入、取最小、抽取最小以外，还支持两个堆的合并（union），
+// 以及通过插入时返回的句柄（handle）对任意元素做关键字减值和删除。
+// 二叉堆做不到高效合并，两个数组拼起来只能重新建堆，是O(n)的。
+//
+// 斐波那契堆：insert、union、decrease_key摊还O(1)，extract_min、delete摊还O(lgn)
+// 二项堆：insert、union、extract_min、decrease_key、delete最坏O(lgn)
+//
+// 节点之间用裸指针连接，句柄和堆共享的部分用Arc和原子操作，键是Send时两种堆都是Send，
+// 每个工作线程可以各自建队列，再把队列交给一个线程合并，句柄在合并后的堆上依然有效
+// 堆不是Sync，同一个堆不能同时被多个线程访问；句柄是Send和Sync，可以随意传递
+
+use std::sync::{ Arc, Mutex };
+
+mod binomial_heap;
+mod fibonacci_heap;
+
+pub use binomial_heap::{ BinomialHandle, BinomialHeap };
+pub use fibonacci_heap::{ FibonacciHandle, FibonacciHeap };
+
+// 最小堆的公共操作
+// 句柄在元素被抽取或删除后失效，对失效的句柄操作会返回错误，而不是访问已释放的节点
+// 一个堆被合并进另一个堆以后，它发出的句柄在新堆上依然有效
+pub trait MergeableHeap {
+    type Key: Ord;
+    type Handle;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert(&mut self, key: Self::Key) -> Self::Handle;
+
+    fn minimum(&self) -> Option<&Self::Key>;
+
+    fn extract_min(&mut self) -> Option<Self::Key>;
+
+    // 把other的所有元素并入self，other被消耗
+    fn union(&mut self, other: Self);
+
+    fn decrease_key(&mut self, handle: &Self::Handle, key: Self::Key) -> Result<(), &'static str>;
+
+    fn delete(&mut self, handle: &Self::Handle) -> Result<Self::Key, &'static str>;
+}
+
+// 堆的身份，用来判断句柄属于哪个堆
+// 合并时把被消耗的堆的身份指向合并后的堆，形成一个并查集，
+// 这样不用逐个修改节点，union依然是O(1)的
+pub(crate) struct Owner {
+    parent: Mutex<Option<Arc<Owner>>>,
+}
+
+impl Owner {
+    pub(crate) fn new() -> Arc<Owner> {
+        Arc::new(Owner { parent: Mutex::new(None) })
+    }
+
+    // 找到根，顺便压缩路径
+    // 每次只锁一个节点；被合并的堆的身份不会再变回根，并发压缩时指向的都是某个祖先，结果不变
+    fn find(owner: &Arc<Owner>) -> Arc<Owner> {
+        let mut root = owner.clone();
+        loop {
+            let next = root.parent.lock().unwrap().clone();
+            match next {
+                Some(parent) => {
+                    root = parent;
+                }
+                None => {
+                    break;
+                }
+            }
+        }
+        let mut cur = owner.clone();
+        while !Arc::ptr_eq(&cur, &root) {
+            let next = cur.parent.lock().unwrap().replace(root.clone()).unwrap();
+            cur = next;
+        }
+        root
+    }
+
+    pub(crate) fn forward(from: &Arc<Owner>, to: &Arc<Owner>) {
+        *from.parent.lock().unwrap() = Some(to.clone());
+    }
+
+    pub(crate) fn owns(heap: &Arc<Owner>, handle: &Arc<Owner>) -> bool {
+        Arc::ptr_eq(&Owner::find(handle), heap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn it_extracts_in_order<H: MergeableHeap<Key = i32> + Default>() {
+        let mut heap = H::default();
+        for key in [22, 43, 145, 1, 9, 43, -7, 0] {
+            heap.insert(key);
+        }
+        assert_eq!(heap.len(), 8);
+        assert_eq!(heap.minimum(), Some(&-7));
+
+        let mut result = vec![];
+        while let Some(key) = heap.extract_min() {
+            result.push(key);
+        }
+        assert_eq!(result, vec![-7, 0, 1, 9, 22, 43, 43, 145]);
+        assert!(heap.is_empty());
+        assert_eq!(heap.extract_min(), None);
+    }
+
+    fn it_unions<H: MergeableHeap<Key = i32> + Default>() {
+        let mut a = H::default();
+        let mut b = H::default();
+        for key in 0..50 {
+            a.insert(key * 2);
+        }
+        let mut handles = vec![];
+        for key in 0..50 {
+            handles.push(b.insert(key * 2 + 1));
+        }
+        a.extract_min(); // 让a先有一些非平凡的树
+        a.union(b);
+        assert_eq!(a.len(), 99);
+
+        // b的句柄在a上依然有效
+        a.decrease_key(&handles[49], -1).unwrap();
+        assert_eq!(a.minimum(), Some(&-1));
+        assert_eq!(a.delete(&handles[10]), Ok(21));
+
+        let mut result = vec![];
+        while let Some(key) = a.extract_min() {
+            result.push(key);
+        }
+        let mut expected: Vec<i32> = (1..99).filter(|&k| k != 21).collect();
+        expected.insert(0, -1);
+        assert_eq!(result, expected);
+    }
+
+    fn it_decreases_and_deletes<H: MergeableHeap<Key = i32> + Default>() {
+        let mut heap = H::default();
+        let handles: Vec<H::Handle> = (0..100).map(|k| heap.insert(k + 100)).collect();
+        heap.extract_min(); // 抽取后斐波那契堆才会合并出多层的树
+        let result = heap.decrease_key(&handles[50], 1000);
+        assert_eq!(result, Err("New key is greater than current key"));
+
+        // 倒序减值，深处的节点被切下，触发级联切断
+        for i in (1..100).rev() {
+            heap.decrease_key(&handles[i], i as i32 - 100).unwrap();
+        }
+        assert_eq!(heap.minimum(), Some(&-99));
+        for i in (1..100).step_by(3) {
+            assert_eq!(heap.delete(&handles[i]), Ok(i as i32 - 100));
+        }
+
+        let mut result = vec![];
+        while let Some(key) = heap.extract_min() {
+            result.push(key);
+        }
+        let expected: Vec<i32> = (1..100)
+            .filter(|i| (i - 1) % 3 != 0)
+            .map(|i| i - 100)
+            .collect();
+        let mut sorted = expected.clone();
+        sorted.sort();
+        assert_eq!(result, sorted);
+    }
+
+    fn it_invalidates_handles<H: MergeableHeap<Key = i32> + Default>() {
+        let mut heap = H::default();
+        let handle = heap.insert(1);
+        heap.insert(2);
+        assert_eq!(heap.extract_min(), Some(1));
+        assert_eq!(heap.decrease_key(&handle, 0), Err("Handle is no longer valid"));
+        assert_eq!(heap.delete(&handle), Err("Handle is no longer valid"));
+
+        let mut other = H::default();
+        let foreign = other.insert(0);
+        assert_eq!(heap.delete(&foreign), Err("Handle does not belong to this heap"));
+        drop(other);
+        assert_eq!(heap.delete(&foreign), Err("Handle is no longer valid"));
+    }
+
+    // 每个线程建自己的队列，交回主线程合并，线程里拿到的句柄在合并后的堆上依然有效
+    fn it_merges_across_threads<H>()
+        where H: MergeableHeap<Key = i32> + Default + Send + 'static, H::Handle: Send + 'static
+    {
+        let workers: Vec<_> = (0..4)
+            .map(|w| {
+                std::thread::spawn(move || {
+                    let mut heap = H::default();
+                    let handles: Vec<H::Handle> = (0..100)
+                        .map(|k| heap.insert(k * 4 + w))
+                        .collect();
+                    heap.extract_min();
+                    (heap, handles)
+                })
+            })
+            .collect();
+
+        let mut merged = H::default();
+        let mut handles = vec![];
+        for worker in workers {
+            let (heap, worker_handles) = worker.join().unwrap();
+            merged.union(heap);
+            handles.push(worker_handles);
+        }
+        assert_eq!(merged.len(), 396);
+
+        // 再把合并后的堆交给另一个线程，在那里用各个工作线程的句柄
+        let consumer = std::thread::spawn(move || {
+            merged.decrease_key(&handles[3][99], -1).unwrap();
+            assert_eq!(merged.delete(&handles[2][50]), Ok(202));
+            assert_eq!(merged.delete(&handles[1][0]), Err("Handle is no longer valid"));
+            merged
+        });
+        let mut merged = consumer.join().unwrap();
+        let mut result = vec![];
+        while let Some(key) = merged.extract_min() {
+            result.push(key);
+        }
+        let mut expected: Vec<i32> = (4..400).filter(|&k| k != 202 && k != 399).collect();
+        expected.insert(0, -1);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn it_fibonacci_heap() {
+        it_extracts_in_order::<FibonacciHeap<i32>>();
+        it_unions::<FibonacciHeap<i32>>();
+        it_decreases_and_deletes::<FibonacciHeap<i32>>();
+        it_invalidates_handles::<FibonacciHeap<i32>>();
+        it_merges_across_threads::<FibonacciHeap<i32>>();
+    }
+
+    #[test]
+    fn it_binomial_heap() {
+        it_extracts_in_order::<BinomialHeap<i32>>();
+        it_unions::<BinomialHeap<i32>>();
+        it_decreases_and_deletes::<BinomialHeap<i32>>();
+        it_invalidates_handles::<BinomialHeap<i32>>();
+        it_merges_across_threads::<BinomialHeap<i32>>();
+    }
+
+    #[test]
+    fn it_drops_owned_keys() {
+        // 带堆内存的元素，配合miri/valgrind检查泄漏和重复释放
+        let mut fib = FibonacciHeap::new();
+        let mut bin = BinomialHeap::new();
+        for i in 0..64 {
+            fib.insert(format!("{:03}", i));
+            bin.insert(format!("{:03}", i));
+        }
+        assert_eq!(fib.extract_min(), Some("000".to_string()));
+        assert_eq!(bin.extract_min(), Some("000".to_string()));
+    }
+}
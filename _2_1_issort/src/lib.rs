@@ -1,5 +1,5 @@
 use std::ptr;
-use algorithms_prelude::CompareSorter;
+use algorithms_prelude::{ CompareSorter, VecSorter };
 
 // 1.直接实现：需要Copy Trait，如果只是做基础数据类型不用考虑太多
 
@@ -9,7 +9,7 @@ use algorithms_prelude::CompareSorter;
 
 // 要清楚哪些环节是“没有银弹”的，设计了一个比较泛用的接口，但是实现不泛用，要一个个实现。
 // 在这里，序列如何排序是要各自实现的，而元素类型是交给钩子函数实现的，所以元素类型泛用，序列类型不泛用
-pub struct InsertionSorter<'a, Seq>(pub &'a mut Seq);
+pub struct InsertionSorter<'a, Seq: ?Sized>(pub &'a mut Seq);
 
 // impl<'a, Elem: Default> Sorter for InsertionSorter<'a, Vec<Elem>> {
 //     // type Sequence = Vec<Elem>;
@@ -121,9 +121,26 @@ pub struct InsertionSorter<'a, Seq>(pub &'a mut Seq);
 // 4. 子循环寻找与目标比较的过程是一个顺序查找的过程，但整个插入排序是一个递归过程，
 //    对于每一个vec[i]，vec[0]..=vec[i-1]是一个已排序的序列，所以可以用二分查找法以O(lgn)的速度查找。
 // 最终这个插入排序的复杂度是O(nlgn)
+// 5. 序列也可以是切片，这样能只排序Vec的一段，其他排序算法排到小区间时可以交给插入排序
 impl<'a, Elem> CompareSorter for InsertionSorter<'a, Vec<Elem>> {
     type Element = Elem;
 
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        InsertionSorter(self.0.as_mut_slice()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for InsertionSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        InsertionSorter(vec).sort_by(compare);
+    }
+}
+
+// 6. 间接排序不用每个Sorter各写一遍，统一用permutation::arg_sort_with，把怎么排下标交给它
+// | arg_sort_with(&vec, compare, |indexed, compare| InsertionSorter(indexed).sort_by(compare))
+impl<'a, Elem> CompareSorter for InsertionSorter<'a, [Elem]> {
+    type Element = Elem;
+
     fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
         let vec = &mut self.0;
         let len = vec.len();
@@ -173,6 +190,7 @@ impl<'a, Elem> CompareSorter for InsertionSorter<'a, Vec<Elem>> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use algorithms_prelude::permutation;
     #[test]
     fn it_sort_ascending() {
         let mut v = vec![22, 43, 145, 1, 9];
@@ -383,4 +401,23 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn it_sort_sub_slice() {
+        let mut v = vec![22, 43, 145, 1, 9, 0];
+        InsertionSorter(&mut v[1..5]).sort_by(|prev, next| prev < next);
+        assert_eq!(v, vec![22, 1, 9, 43, 145, 0]);
+    }
+
+    #[test]
+    fn it_arg_sort_stable() {
+        let v = vec![(2, "a"), (1, "b"), (2, "c"), (0, "d"), (1, "e")];
+        let compare = |prev: &(i32, &str), next: &(i32, &str)| prev.0 <= next.0;
+        let perm = permutation::arg_sort_with(&v, compare, |indexed, compare| {
+            InsertionSorter(indexed).sort_by(compare)
+        });
+        // 相等的键保持原来的先后，原序列不动
+        assert_eq!(perm, vec![3, 1, 4, 0, 2]);
+        assert_eq!(v[0], (2, "a"));
+    }
 }
@@ -1,91 +1,125 @@
-use std::{ mem::{ forget, ManuallyDrop }, ptr };
-use algorithms_prelude::CompareSorter;
+use std::{ mem, ptr };
+use algorithms_prelude::{ CompareSorter, VecSorter };
 
-pub struct MergeSorter<'a, Seq>(pub &'a mut Seq);
+pub struct MergeSorter<'a, Seq: ?Sized>(pub &'a mut Seq);
 
 impl<'a, Elem> CompareSorter for MergeSorter<'a, Vec<Elem>> {
     type Element = Elem;
 
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        MergeSorter(self.0.as_mut_slice()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for MergeSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        MergeSorter(vec).sort_by(compare);
+    }
+}
+
+// 自底向上的归并排序
+// 递归的版本每层都要为左右两半各分配一次临时数组，这里只分配一个和原序列等长的缓冲区，
+// 宽度为1、2、4……的相邻两段两两归并，在原序列和缓冲区之间来回倒，
+// 没有递归，栈空间O(1)，堆上只有这一次分配
+impl<'a, Elem> CompareSorter for MergeSorter<'a, [Elem]> {
+    type Element = Elem;
+
     fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
         let vec = &mut self.0;
+        let len = vec.len();
 
-        if vec.len() < 2 {
+        if len < 2 {
             return;
         }
 
-        unsafe {
-            let p = vec.as_mut_ptr().cast::<ManuallyDrop<Elem>>();
-            let len = vec.len();
-            let cap = vec.capacity();
-            let mut temp = Vec::from_raw_parts(p, len, cap);
-            merge_sort(&mut temp, vec, compare, 0, vec.len());
-            forget(temp);
+        // 缓冲区的长度始终为0，元素只是按位拷贝进出，drop时只释放内存，不会drop元素
+        // 它要比passes活得久，所以先声明
+        let mut buffer: Vec<Elem> = Vec::with_capacity(len);
+        let mut passes = MergePasses {
+            src: vec.as_mut_ptr(),
+            dst: buffer.as_mut_ptr(),
+            origin: vec.as_mut_ptr(),
+            len,
+        };
+        let mut width = 1;
+        while width < len {
+            let mut p = 0;
+            while p < len {
+                let q = (p + width).min(len);
+                let r = (p + (width << 1)).min(len);
+                unsafe {
+                    let src = passes.src;
+                    merge(src.add(p), q - p, src.add(q), r - q, passes.dst.add(p), compare);
+                }
+                p = r;
+            }
+            mem::swap(&mut passes.src, &mut passes.dst);
+            width <<= 1;
         }
     }
 }
 
-fn merge_sort<T>(
-    temp: &mut Vec<ManuallyDrop<T>>,
-    vec: &Vec<T>,
-    compare: fn(prev: &T, next: &T) -> bool,
-    p: usize,
-    r: usize
-) {
-    if r - p > 1 {
-        let q = (p + 1 + r) >> 1; // 为了让左子树先大，整个域右移一格（或者说是结果向上取整）
-        unsafe {
-            let mut left = Vec::<ManuallyDrop<T>>::with_capacity(q - p);
-            left.set_len(q - p);
-            merge_sort(&mut left, vec, compare, p, q);
-
-            let mut right = Vec::<ManuallyDrop<T>>::with_capacity(r - q);
-            right.set_len(r - q);
-            merge_sort(&mut right, vec, compare, q, r);
-            merge(temp, compare, left, right);
-        }
-    } else {
-        // 叶子，终止
-        unsafe {
-            ptr::copy((&vec[p] as *const T).cast::<ManuallyDrop<T>>(), &mut temp[0], 1);
+// 每一轮只从src读、往dst写，元素是按位拷贝而不是移走，所以任何时刻src里都恰好有每个元素一份
+// drop时src不是原序列就拷回去：正常结束时拷回最后一轮的结果，
+// compare中途panic时拷回上一轮完整的结果，原序列里不会有重复的元素，展开时也就不会重复drop
+struct MergePasses<T> {
+    src: *mut T,
+    dst: *mut T,
+    origin: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for MergePasses<T> {
+    fn drop(&mut self) {
+        if self.src != self.origin {
+            unsafe {
+                ptr::copy_nonoverlapping(self.src, self.origin, self.len);
+            }
         }
     }
 }
 
-fn merge<T>(
-    temp: &mut Vec<ManuallyDrop<T>>,
-    compare: fn(prev: &T, next: &T) -> bool,
-    left: Vec<ManuallyDrop<T>>,
-    right: Vec<ManuallyDrop<T>>
+// 把left和right两段归并到temp
+// 比如升序，就是每次都取两个牌堆顶最小的牌，compare包含等于时优先取左边的，所以是稳定的
+unsafe fn merge<T>(
+    left: *const T,
+    left_length: usize,
+    right: *const T,
+    right_length: usize,
+    temp: *mut T,
+    compare: fn(prev: &T, next: &T) -> bool
 ) {
-    let left_length = left.len();
-    let right_length = right.len();
     let mut i = 0;
     let mut j = 0;
     let mut k = 0;
 
-    unsafe {
-        while i < left_length && j < right_length {
-            if compare(&left[i], &right[j]) {
-                ptr::copy(&left[i], &mut temp[k], 1);
-                i += 1;
-            } else {
-                ptr::copy(&right[j], &mut temp[k], 1);
-                j += 1;
-            }
-            k += 1;
+    while i < left_length && j < right_length {
+        if compare(&*left.add(i), &*right.add(j)) {
+            ptr::copy_nonoverlapping(left.add(i), temp.add(k), 1);
+            i += 1;
+        } else {
+            ptr::copy_nonoverlapping(right.add(j), temp.add(k), 1);
+            j += 1;
         }
+        k += 1;
+    }
 
-        if i < left_length {
-            ptr::copy(&left[i], &mut temp[k], left_length - i);
-        } else if j < right_length {
-            ptr::copy(&right[j], &mut temp[k], right_length - j);
-        }
+    if i < left_length {
+        ptr::copy_nonoverlapping(left.add(i), temp.add(k), left_length - i);
+    } else if j < right_length {
+        ptr::copy_nonoverlapping(right.add(j), temp.add(k), right_length - j);
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::{ cell::Cell, panic::{ self, AssertUnwindSafe } };
+    use algorithms_prelude::{
+        permutation,
+        random::XorShift64,
+        stack::{ run_with_stack, SMALL_STACK },
+    };
     #[test]
     fn it_sort_ascending() {
         let mut v = vec![22, 43, 145, 1, 9];
@@ -292,4 +326,117 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn it_sort_all_lengths_stable() {
+        // 各种长度都会遇到末尾不满一段的情况
+        for len in 0..70 {
+            let mut v: Vec<(usize, usize)> = (0..len).map(|i| ((i * 7) % 5, i)).collect();
+            let mut expected = v.clone();
+            expected.sort_by_key(|e| e.0); // 标准库的sort_by_key是稳定的
+            MergeSorter(&mut v).sort_by(|prev, next| prev.0 <= next.0);
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[test]
+    fn it_sort_with_small_stack() {
+        let mut random = XorShift64::new(6);
+        let mut v: Vec<u64> = (0..100_000).map(|_| random.next_u64()).collect();
+        run_with_stack(SMALL_STACK, || MergeSorter(&mut v).sort_by(|prev, next| prev <= next));
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    // 逆序输入，每次归并都要把右段整个搬到左段前面
+    // 先升后降的输入，每次归并两段都要交替地取；键全部相同时检查稳定
+    fn sort_adversarial_with_small_stack(len: usize) {
+        run_with_stack(SMALL_STACK, || {
+            let mut v: Vec<usize> = (0..len).rev().collect();
+            MergeSorter(&mut v).sort_by(|prev, next| prev <= next);
+            assert_eq!(v, (0..len).collect::<Vec<_>>());
+
+            let mut v: Vec<usize> = (0..len).map(|i| i.min(len - 1 - i)).collect();
+            let mut expected = v.clone();
+            expected.sort();
+            MergeSorter(&mut v).sort_by(|prev, next| prev <= next);
+            assert_eq!(v, expected);
+
+            let mut v: Vec<(u8, usize)> = (0..len).map(|i| (0, i)).collect();
+            let expected = v.clone();
+            MergeSorter(&mut v).sort_by(|prev, next| prev.0 <= next.0);
+            assert_eq!(v, expected);
+        });
+    }
+
+    #[test]
+    fn it_sort_adversarial_with_small_stack() {
+        sort_adversarial_with_small_stack(100_000);
+    }
+
+    #[test]
+    #[ignore = "10^7个元素，用cargo test --release -- --ignored运行"]
+    fn it_sort_ten_million_with_small_stack() {
+        sort_adversarial_with_small_stack(10_000_000);
+    }
+
+    #[test]
+    fn it_survive_panicking_compare() {
+        // compare在第limit次比较时panic，原序列依然是原来那些元素的一个排列，每个元素恰好drop一次
+        thread_local! {
+            static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Debug)]
+        struct Tracked(Box<u32>);
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                DROPS.set(DROPS.get() + 1);
+            }
+        }
+
+        fn compare(prev: &Tracked, next: &Tracked) -> bool {
+            COMPARISONS.set(COMPARISONS.get() - 1);
+            if COMPARISONS.get() == 0 {
+                panic!("compare failed");
+            }
+            prev.0 <= next.0
+        }
+
+        let len = 100;
+        // 每一轮大约比较len次，这些位置分别落在第1、2、3……轮中，src有时是原序列，有时是缓冲区
+        for limit in [1, 50, 150, 250, 350, 450] {
+            let mut random = XorShift64::new(limit as u64);
+            let mut v: Vec<Tracked> = (0..len)
+                .map(|_| Tracked(Box::new(random.below(1000) as u32)))
+                .collect();
+            let mut expected: Vec<u32> = v.iter().map(|e| *e.0).collect();
+            COMPARISONS.set(limit);
+            DROPS.set(0);
+            let result = panic::catch_unwind(
+                AssertUnwindSafe(|| MergeSorter(&mut v).sort_by(compare))
+            );
+            assert!(result.is_err());
+            assert_eq!(DROPS.get(), 0);
+
+            let mut keys: Vec<u32> = v.iter().map(|e| *e.0).collect();
+            keys.sort();
+            expected.sort();
+            assert_eq!(keys, expected);
+            drop(v);
+            assert_eq!(DROPS.get(), len);
+        }
+    }
+
+    #[test]
+    fn it_arg_sort_stable() {
+        let mut random = XorShift64::new(36);
+        let v: Vec<u8> = (0..1000).map(|_| (random.next_u64() % 10) as u8).collect();
+        let perm = permutation::arg_sort_with(&v, |prev, next| prev <= next, |indexed, compare| {
+            MergeSorter(indexed).sort_by(compare)
+        });
+        // 键升序，键相同时下标升序
+        assert!(perm.windows(2).all(|w| (v[w[0]], w[0]) < (v[w[1]], w[1])));
+    }
 }
@@ -1,70 +1,47 @@
-// 递归解决，划定一个中点，此时有三种情况：
-// 最大子数组在中点左边（递归）
-// 在中点右边（递归）
-// 跨过中点（终止），中点左边的一半取最大，右边一半取最大，组合起来得最大
+mod max_subarray;
+mod submatrix;
+mod variants;
 
-use std::i32::MIN;
+pub use max_subarray::{
+    max_subarray,
+    EmptySubarray,
+    ExactSubarraySum,
+    MaxSubarray,
+    MaxSubarrayFinder,
+    SubarraySum,
+    TieBreak,
+};
+pub use submatrix::{ max_submatrix, MaxSubmatrix };
+pub use variants::{
+    bounded_max_subarray,
+    circular_max_subarray,
+    greedy_top_k_max_subarrays,
+    max_subarray_with_removals,
+};
+
+// 书中的分治见MaxSubarrayFinder::divide_and_conquer，这里建立在kadane上：
+// 从左往右扫描一遍，O(n)，没有递归，栈空间O(1)
+// 和相同的子数组有多个时取最短的，长度也相同时取最靠左的
 
 /**
  * 寻找最大子数组
+ * 在[low, high)中寻找，返回的左右下标是全闭的，空区间返回(0, 0, 0)
+ * 求和在i128里进行，结果超出i32时panic
  */
-pub fn find_maximum_subarray(vec: &Vec<i32>, low: usize, high: usize) -> (usize, usize, i32) {
-    if vec.len() == 0 {
-        (0, 0, 0)
-    } else if high - low <= 1 {
-        (low, low, vec[low])
-    } else {
-        // 中点分界左闭右开，参考归并排序的实现
-        // 代入的值和返回的值无关，所以这里high和left/right/cross_high不同也没关系
-        // 区间是左闭右开，但结果的左右指针是全闭
-        let mid = (low + high + 1) >> 1;
-        let (left_low, left_high, left_sum) = find_maximum_subarray(vec, low, mid);
-        let (right_low, right_high, right_sum) = find_maximum_subarray(vec, mid, high);
-        let (cross_low, cross_high, cross_sum) = find_max_crossing_subarray(vec, low, mid, high);
-
-        if left_sum >= right_sum && left_sum >= cross_sum {
-            (left_low, left_high, left_sum)
-        } else if right_sum >= left_sum && right_sum >= cross_sum {
-            (right_low, right_high, right_sum)
-        } else {
-            (cross_low, cross_high, cross_sum)
-        }
-    }
-}
-
-fn find_max_crossing_subarray(
-    vec: &Vec<i32>,
-    low: usize,
-    mid: usize,
-    high: usize
-) -> (usize, usize, i32) {
-    let mut left_sum = MIN;
-    let mut sum = 0;
-    let mut max_left = mid - 1;
-    for i in (low..mid).rev() {
-        sum += vec[i];
-        if sum > left_sum {
-            left_sum = sum;
-            max_left = i;
-        }
-    }
-
-    let mut right_sum = MIN;
-    sum = 0;
-    let mut max_right = mid;
-    for j in mid..high {
-        sum += vec[j];
-        if sum >= right_sum {
-            right_sum = sum;
-            max_right = j;
+pub fn find_maximum_subarray(vec: &[i32], low: usize, high: usize) -> (usize, usize, i32) {
+    match max_subarray(&vec[low..high]) {
+        Some(MaxSubarray { range, sum }) => {
+            let sum = i32::try_from(sum).expect("最大子数组的和超出i32");
+            (low + range.start, low + range.end - 1, sum)
         }
+        None => (0, 0, 0),
     }
-    (max_left, max_right, left_sum + right_sum)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use algorithms_prelude::{ random::XorShift64, stack::{ run_with_stack, SMALL_STACK } };
 
     #[test]
     fn it_works() {
@@ -72,4 +49,36 @@ mod tests {
         let result = find_maximum_subarray(&vec, 0, vec.len());
         assert_eq!(result, (7, 10, 43)); // 注意下标和书中不同
     }
+
+    #[test]
+    fn it_works_with_small_stack() {
+        // 全是负数时答案是最大的那个元素，全是正数时是整个数组，正负交替时和相同的子数组最多
+        let n = 100_000;
+        run_with_stack(SMALL_STACK, || {
+            let mut vec = vec![-1; n];
+            vec[n / 3] = 5;
+            assert_eq!(find_maximum_subarray(&vec, 0, n), (n / 3, n / 3, 5));
+
+            let vec = vec![1; n];
+            assert_eq!(find_maximum_subarray(&vec, 0, n), (0, n - 1, n as i32));
+
+            let vec: Vec<i32> = (0..n).map(|i| if i % 2 == 0 { 1 } else { -1 }).collect();
+            assert_eq!(find_maximum_subarray(&vec, 0, n), (0, 0, 1));
+            assert_eq!(find_maximum_subarray(&vec, 1, n), (2, 2, 1));
+
+            let vec: Vec<i32> = (0..n as i32).map(|i| i - (n as i32)).collect();
+            assert_eq!(find_maximum_subarray(&vec, 0, n), (n - 1, n - 1, -1));
+        });
+    }
+
+    #[test]
+    #[ignore = "10^7个元素，用cargo test --release -- --ignored运行"]
+    fn it_works_ten_million_with_small_stack() {
+        let mut random = XorShift64::new(9);
+        let vec: Vec<i32> = (0..10_000_000).map(|_| (random.below(201) as i32) - 100).collect();
+        let (low, high, sum) = run_with_stack(SMALL_STACK, || {
+            find_maximum_subarray(&vec, 0, vec.len())
+        });
+        assert_eq!(vec[low..=high].iter().sum::<i32>(), sum);
+    }
 }
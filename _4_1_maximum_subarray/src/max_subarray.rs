@@ -0,0 +1,374 @@
+use std::ops::Range;
+
+// 泛型的最大子数组
+// find_maximum_subarray只能用于i32，空输入返回(0, 0, 0)，和超出i32时panic
+// 这里元素可以是有符号整数或者浮点数，求和在更宽的类型里进行：
+// 64位以内的整数加宽到i128，元素个数不会超过2^64，所以和不会溢出；i128本身用checked_add，溢出时panic
+// f32加宽到f64，f64直接相加
+// 结果是半开区间range和它的和sum，没有可选的子数组（空输入且不允许空子数组）时为None
+//
+// 三种解法（练习4.1-5）：
+// brute_force 枚举所有子数组，O(n^2)
+// divide_and_conquer 书中的分治，O(nlgn)
+// kadane 从左往右扫描，维护以当前元素结尾的最大子数组，O(n)
+
+// range一般在[0, n)之内；环形数组（MaxSubarrayFinder::circular）的子数组跨过末尾时range.end > n，
+// 这时range.start < n，长度不超过n，第i个元素是slice[i % n]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxSubarray<S> {
+    pub range: Range<usize>,
+    pub sum: S,
+}
+
+// 和相同的子数组有多个时选哪一个，长度也相同时总是选最靠左的
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    #[default]
+    Shortest,
+    Longest,
+}
+
+// 是否允许空子数组
+// 允许时空子数组（range为0..0，和为0）也参与比较，元素全为负数时就返回它
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptySubarray {
+    #[default]
+    Disallow,
+    Allow,
+}
+
+pub trait SubarraySum: Copy {
+    type Sum: Copy + PartialOrd;
+
+    fn zero() -> Self::Sum;
+
+    fn widen(self) -> Self::Sum;
+
+    fn add(a: Self::Sum, b: Self::Sum) -> Self::Sum;
+}
+
+// 和是精确的整数，两个前缀和相减就是中间那一段的和，结果是某一段的和，所以和add一样不会溢出
+// 浮点数不实现：前缀和里前面的大数会吃掉后面小数的有效位，相减时误差全留在差里，
+// 比如[-1e17, 2.0, 3.0]的后三个前缀和都是-1e17，两两相减全是0
+pub trait ExactSubarraySum: SubarraySum {
+    fn sub(a: Self::Sum, b: Self::Sum) -> Self::Sum;
+}
+
+macro_rules! impl_subarray_sum_widened {
+    ($($t:ty),*) => {
+        $(
+            impl SubarraySum for $t {
+                type Sum = i128;
+
+                fn zero() -> i128 {
+                    0
+                }
+
+                fn widen(self) -> i128 {
+                    self as i128
+                }
+
+                fn add(a: i128, b: i128) -> i128 {
+                    a + b
+                }
+            }
+
+            impl ExactSubarraySum for $t {
+                fn sub(a: i128, b: i128) -> i128 {
+                    a - b
+                }
+            }
+        )*
+    };
+}
+
+impl_subarray_sum_widened!(i8, i16, i32, i64, isize);
+
+impl SubarraySum for i128 {
+    type Sum = i128;
+
+    fn zero() -> i128 {
+        0
+    }
+
+    fn widen(self) -> i128 {
+        self
+    }
+
+    fn add(a: i128, b: i128) -> i128 {
+        a.checked_add(b).expect("最大子数组的和溢出")
+    }
+}
+
+impl ExactSubarraySum for i128 {
+    fn sub(a: i128, b: i128) -> i128 {
+        a.checked_sub(b).expect("最大子数组的和溢出")
+    }
+}
+
+macro_rules! impl_subarray_sum_float {
+    ($($t:ty),*) => {
+        $(
+            impl SubarraySum for $t {
+                type Sum = f64;
+
+                fn zero() -> f64 {
+                    0.0
+                }
+
+                fn widen(self) -> f64 {
+                    self as f64
+                }
+
+                fn add(a: f64, b: f64) -> f64 {
+                    a + b
+                }
+            }
+        )*
+    };
+}
+
+impl_subarray_sum_float!(f32, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MaxSubarrayFinder {
+    pub(crate) tie_break: TieBreak,
+    pub(crate) empty: EmptySubarray,
+}
+
+impl MaxSubarrayFinder {
+    pub fn new() -> MaxSubarrayFinder {
+        MaxSubarrayFinder::default()
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> MaxSubarrayFinder {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn empty(mut self, empty: EmptySubarray) -> MaxSubarrayFinder {
+        self.empty = empty;
+        self
+    }
+
+    // a是否比b好：和更大；和相同时按TieBreak比长度；长度也相同时更靠左
+    pub(crate) fn better<S: PartialOrd>(&self, a: &MaxSubarray<S>, b: &MaxSubarray<S>) -> bool {
+        if a.sum != b.sum {
+            return a.sum > b.sum;
+        }
+        let (a_len, b_len) = (a.range.len(), b.range.len());
+        if a_len != b_len {
+            return match self.tie_break {
+                TieBreak::Shortest => a_len < b_len,
+                TieBreak::Longest => a_len > b_len,
+            };
+        }
+        a.range.start < b.range.start
+    }
+
+    // 按EmptySubarray和空子数组比较
+    pub(crate) fn finish<S: Copy + PartialOrd>(
+        &self,
+        best: Option<MaxSubarray<S>>,
+        zero: S
+    ) -> Option<MaxSubarray<S>> {
+        if self.empty == EmptySubarray::Disallow {
+            return best;
+        }
+        let empty = MaxSubarray { range: 0..0, sum: zero };
+        match best {
+            Some(best) if !self.better(&empty, &best) => Some(best),
+            _ => Some(empty),
+        }
+    }
+
+    pub fn brute_force<T: SubarraySum>(&self, slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+        let mut best: Option<MaxSubarray<T::Sum>> = None;
+        for i in 0..slice.len() {
+            let mut sum = T::zero();
+            for (j, &e) in slice.iter().enumerate().skip(i) {
+                sum = T::add(sum, e.widen());
+                let candidate = MaxSubarray { range: i..j + 1, sum };
+                if best.as_ref().is_none_or(|best| self.better(&candidate, best)) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        self.finish(best, T::zero())
+    }
+
+    pub fn divide_and_conquer<T: SubarraySum>(&self, slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+        let best = if slice.is_empty() {
+            None
+        } else {
+            Some(self.maximum_in(slice, 0, slice.len()))
+        };
+        self.finish(best, T::zero())
+    }
+
+    // [low, high)非空
+    fn maximum_in<T: SubarraySum>(
+        &self,
+        slice: &[T],
+        low: usize,
+        high: usize
+    ) -> MaxSubarray<T::Sum> {
+        if high - low == 1 {
+            return MaxSubarray { range: low..high, sum: slice[low].widen() };
+        }
+        let mid = low + (high - low) / 2;
+        let mut best = self.maximum_in(slice, low, mid);
+        let right = self.maximum_in(slice, mid, high);
+        let cross = self.maximum_crossing(slice, low, mid, high);
+        for candidate in [right, cross] {
+            if self.better(&candidate, &best) {
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    // 跨过中点的子数组由左半边的最大后缀和右半边的最大前缀组成，两边的长度按TieBreak各自取最短或最长
+    fn maximum_crossing<T: SubarraySum>(
+        &self,
+        slice: &[T],
+        low: usize,
+        mid: usize,
+        high: usize
+    ) -> MaxSubarray<T::Sum> {
+        let longest = self.tie_break == TieBreak::Longest;
+
+        let mut sum = T::zero();
+        let mut left_sum = None;
+        let mut start = mid;
+        for i in (low..mid).rev() {
+            sum = T::add(sum, slice[i].widen());
+            if left_sum.is_none_or(|left_sum| sum > left_sum || (longest && sum == left_sum)) {
+                left_sum = Some(sum);
+                start = i;
+            }
+        }
+
+        let mut sum = T::zero();
+        let mut right_sum = None;
+        let mut end = mid;
+        for (j, &e) in slice.iter().enumerate().take(high).skip(mid) {
+            sum = T::add(sum, e.widen());
+            if right_sum.is_none_or(|right_sum| sum > right_sum || (longest && sum == right_sum)) {
+                right_sum = Some(sum);
+                end = j + 1;
+            }
+        }
+
+        MaxSubarray { range: start..end, sum: T::add(left_sum.unwrap(), right_sum.unwrap()) }
+    }
+
+    // 以第j个元素结尾的最大子数组，要么只有它自己，要么是以第j - 1个元素结尾的最大子数组再加上它
+    // 前者的和为正时接上去，为负时从j重新开始，为0时两者和相同，按TieBreak取长的或短的
+    pub fn kadane<T: SubarraySum>(&self, slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+        let zero = T::zero();
+        let longest = self.tie_break == TieBreak::Longest;
+        let mut best: Option<MaxSubarray<T::Sum>> = None;
+        let mut current: Option<MaxSubarray<T::Sum>> = None;
+        for (j, &e) in slice.iter().enumerate() {
+            let e = e.widen();
+            let next = match current {
+                Some(current) if current.sum > zero || (longest && current.sum == zero) => {
+                    MaxSubarray { range: current.range.start..j + 1, sum: T::add(current.sum, e) }
+                }
+                _ => MaxSubarray { range: j..j + 1, sum: e },
+            };
+            if best.as_ref().is_none_or(|best| self.better(&next, best)) {
+                best = Some(next.clone());
+            }
+            current = Some(next);
+        }
+        self.finish(best, zero)
+    }
+}
+
+// 默认选项（最短、不允许空子数组）的kadane
+pub fn max_subarray<T: SubarraySum>(slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+    MaxSubarrayFinder::new().kadane(slice)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::random::XorShift64;
+
+    fn finders() -> Vec<MaxSubarrayFinder> {
+        let mut finders = vec![];
+        for tie_break in [TieBreak::Shortest, TieBreak::Longest] {
+            for empty in [EmptySubarray::Disallow, EmptySubarray::Allow] {
+                finders.push(MaxSubarrayFinder::new().tie_break(tie_break).empty(empty));
+            }
+        }
+        finders
+    }
+
+    #[test]
+    fn it_find_book_example() {
+        let vec: Vec<i32> = vec![13, -3, -25, 20, -3, -16, -23, 18, 20, -7, 12, -5, -22, 15, -4, 7];
+        let expected = Some(MaxSubarray { range: 7..11, sum: 43 });
+        assert_eq!(max_subarray(&vec), expected);
+        for finder in finders() {
+            assert_eq!(finder.divide_and_conquer(&vec), expected);
+            assert_eq!(finder.brute_force(&vec), expected);
+        }
+    }
+
+    #[test]
+    fn it_agree_with_brute_force() {
+        let mut random = XorShift64::new(48);
+        for _ in 0..500 {
+            // 值域小，容易出现和相同的子数组和前缀和为0的情况
+            let len = random.below(20);
+            let vec: Vec<i8> = (0..len).map(|_| (random.below(7) as i8) - 3).collect();
+            for finder in finders() {
+                let expected = finder.brute_force(&vec);
+                assert_eq!(finder.kadane(&vec), expected, "{:?} {:?}", finder, vec);
+                assert_eq!(finder.divide_and_conquer(&vec), expected, "{:?} {:?}", finder, vec);
+            }
+        }
+    }
+
+    #[test]
+    fn it_apply_tie_break_and_empty_policies() {
+        let vec: [i32; 7] = [-1, 0, 2, -2, 2, 0, -5];
+        let finder = MaxSubarrayFinder::new();
+        assert_eq!(finder.kadane(&vec), Some(MaxSubarray { range: 2..3, sum: 2 }));
+        let finder = finder.tie_break(TieBreak::Longest);
+        assert_eq!(finder.kadane(&vec), Some(MaxSubarray { range: 1..6, sum: 2 }));
+
+        let negative = [-3.5f32, -0.5, -2.0];
+        assert_eq!(max_subarray(&negative), Some(MaxSubarray { range: 1..2, sum: -0.5 }));
+        let finder = MaxSubarrayFinder::new().empty(EmptySubarray::Allow);
+        assert_eq!(finder.kadane(&negative), Some(MaxSubarray { range: 0..0, sum: 0.0 }));
+        let empty = Some(MaxSubarray { range: 0..0, sum: 0.0 });
+        assert_eq!(finder.divide_and_conquer::<f64>(&[]), empty);
+        assert_eq!(max_subarray::<f64>(&[]), None);
+
+        // 和为0时，最短的是空子数组，最长的是整个数组
+        let zeros = [0i64, 0];
+        assert_eq!(finder.kadane(&zeros), Some(MaxSubarray { range: 0..0, sum: 0 }));
+        let finder = finder.tie_break(TieBreak::Longest);
+        assert_eq!(finder.kadane(&zeros), Some(MaxSubarray { range: 0..2, sum: 0 }));
+    }
+
+    #[test]
+    fn it_widen_sum() {
+        let vec = vec![i64::MAX; 4];
+        let result = max_subarray(&vec).unwrap();
+        assert_eq!(result.range, 0..4);
+        assert_eq!(result.sum, (i64::MAX as i128) * 4);
+        let vec = vec![i8::MIN, i8::MAX, i8::MAX, i8::MIN];
+        assert_eq!(max_subarray(&vec), Some(MaxSubarray { range: 1..3, sum: 254 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "最大子数组的和溢出")]
+    fn it_panic_on_i128_overflow() {
+        max_subarray(&[i128::MAX, 1]);
+    }
+}
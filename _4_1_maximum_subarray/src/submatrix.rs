@@ -0,0 +1,236 @@
+use std::ops::Range;
+
+use crate::max_subarray::{ EmptySubarray, MaxSubarrayFinder, SubarraySum, TieBreak };
+
+// 二维的最大子矩阵
+// 枚举上下边界top..bottom，把这几行按列加起来压成一行，对这一行求最大子数组就得到左右边界
+// 固定top以后bottom每往下一行，列和只要各加上新的一行，所以压缩是O(cols)，总共O(rows^2 * cols)
+// 行数比列数多时先转置，枚举的是较短的一边，O(min^2 * max)，求出来的子矩阵再把行列换回去
+//
+// 列和已经是加宽后的Sum类型，对它再求最大子数组要求Sum本身也是SubarraySum，i128和f64都满足
+// 和相同时按TieBreak比面积，面积也相同时依次选最靠上的、最靠左的、最矮的
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxSubmatrix<S> {
+    pub rows: Range<usize>,
+    pub cols: Range<usize>,
+    pub sum: S,
+}
+
+impl<S> MaxSubmatrix<S> {
+    pub fn area(&self) -> usize {
+        self.rows.len() * self.cols.len()
+    }
+}
+
+impl MaxSubarrayFinder {
+    // 矩阵按行给出，每行长度必须相同；没有元素且不允许空子矩阵时为None
+    pub fn submatrix<T>(
+        &self,
+        matrix: &[Vec<T>]
+    ) -> Result<Option<MaxSubmatrix<T::Sum>>, &'static str>
+        where T: SubarraySum, T::Sum: SubarraySum<Sum = T::Sum>
+    {
+        let cols = matrix.first().map_or(0, |row| row.len());
+        if matrix.iter().any(|row| row.len() != cols) {
+            return Err("Rows have different lengths");
+        }
+
+        // 固定上下边界时kadane按和、宽度、最靠左选，转置后固定左右边界时按和、高度、最靠上选，
+        // 都和better_submatrix的顺序一致，所以转不转置结果相同
+        let best = if matrix.len() > cols {
+            let transposed: Vec<Vec<T>> = (0..cols)
+                .map(|j| matrix.iter().map(|row| row[j]).collect())
+                .collect();
+            self.best_nonempty_submatrix(&transposed, true)
+        } else {
+            self.best_nonempty_submatrix(matrix, false)
+        };
+
+        if self.empty == EmptySubarray::Disallow {
+            return Ok(best);
+        }
+        let empty = MaxSubmatrix { rows: 0..0, cols: 0..0, sum: T::zero() };
+        Ok(match best {
+            Some(best) if !self.better_submatrix(&empty, &best) => Some(best),
+            _ => Some(empty),
+        })
+    }
+
+    // 每一对上下边界内部用kadane，空子数组由调用者统一比较
+    // transposed为真时matrix是转置过的，候选的行列换回去以后再比较
+    fn best_nonempty_submatrix<T>(
+        &self,
+        matrix: &[Vec<T>],
+        transposed: bool
+    ) -> Option<MaxSubmatrix<T::Sum>>
+        where T: SubarraySum, T::Sum: SubarraySum<Sum = T::Sum>
+    {
+        let cols = matrix.first().map_or(0, |row| row.len());
+        let finder = self.empty(EmptySubarray::Disallow);
+        let mut best: Option<MaxSubmatrix<T::Sum>> = None;
+        let mut column_sums = vec![T::zero(); cols];
+        for top in 0..matrix.len() {
+            column_sums.fill(T::zero());
+            for (bottom, row) in matrix.iter().enumerate().skip(top) {
+                for (sum, &e) in column_sums.iter_mut().zip(row) {
+                    *sum = T::add(*sum, e.widen());
+                }
+                if let Some(found) = finder.kadane(&column_sums) {
+                    let (rows, cols) = if transposed {
+                        (found.range, top..bottom + 1)
+                    } else {
+                        (top..bottom + 1, found.range)
+                    };
+                    let candidate = MaxSubmatrix { rows, cols, sum: found.sum };
+                    if best.as_ref().is_none_or(|best| self.better_submatrix(&candidate, best)) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn better_submatrix<S: PartialOrd>(&self, a: &MaxSubmatrix<S>, b: &MaxSubmatrix<S>) -> bool {
+        if a.sum != b.sum {
+            return a.sum > b.sum;
+        }
+        let (a_area, b_area) = (a.area(), b.area());
+        if a_area != b_area {
+            return match self.tie_break {
+                TieBreak::Shortest => a_area < b_area,
+                TieBreak::Longest => a_area > b_area,
+            };
+        }
+        (a.rows.start, a.cols.start, a.rows.end) < (b.rows.start, b.cols.start, b.rows.end)
+    }
+}
+
+// 默认选项的最大子矩阵
+pub fn max_submatrix<T>(matrix: &[Vec<T>]) -> Result<Option<MaxSubmatrix<T::Sum>>, &'static str>
+    where T: SubarraySum, T::Sum: SubarraySum<Sum = T::Sum>
+{
+    MaxSubarrayFinder::new().submatrix(matrix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::random::XorShift64;
+
+    // 枚举所有子矩阵，用二维前缀和求和
+    fn brute_force(finder: &MaxSubarrayFinder, matrix: &[Vec<i32>]) -> Option<MaxSubmatrix<i128>> {
+        let rows = matrix.len();
+        let cols = matrix.first().map_or(0, |row| row.len());
+        let mut prefix = vec![vec![0i128; cols + 1]; rows + 1];
+        for i in 0..rows {
+            for j in 0..cols {
+                prefix[i + 1][j + 1] =
+                    (matrix[i][j] as i128) + prefix[i][j + 1] + prefix[i + 1][j] - prefix[i][j];
+            }
+        }
+        let mut best = None;
+        if finder.empty == EmptySubarray::Allow {
+            best = Some(MaxSubmatrix { rows: 0..0, cols: 0..0, sum: 0 });
+        }
+        for top in 0..rows {
+            for left in 0..cols {
+                for bottom in top + 1..=rows {
+                    for right in left + 1..=cols {
+                        let sum =
+                            prefix[bottom][right] - prefix[top][right] - prefix[bottom][left] +
+                            prefix[top][left];
+                        let candidate = MaxSubmatrix { rows: top..bottom, cols: left..right, sum };
+                        let better = best
+                            .as_ref()
+                            .is_none_or(|best| finder.better_submatrix(&candidate, best));
+                        if better {
+                            best = Some(candidate);
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn it_find_hottest_rectangle() -> Result<(), &'static str> {
+        let profit: Vec<Vec<i32>> = vec![
+            vec![0, -2, -7, 0],
+            vec![9, 2, -6, 2],
+            vec![-4, 1, -4, 1],
+            vec![-1, 8, 0, -2]
+        ];
+        let expected = MaxSubmatrix { rows: 1..4, cols: 0..2, sum: 15 };
+        assert_eq!(max_submatrix(&profit)?, Some(expected));
+
+        let readings = vec![vec![-1.5f32, 2.5], vec![-0.5, -3.0]];
+        let result = max_submatrix(&readings)?.unwrap();
+        assert_eq!((result.rows, result.cols, result.sum), (0..1, 1..2, 2.5));
+        Ok(())
+    }
+
+    #[test]
+    fn it_agree_with_brute_force() -> Result<(), &'static str> {
+        let mut random = XorShift64::new(49);
+        for _ in 0..200 {
+            let rows = random.below(6);
+            let cols = random.below(6);
+            let matrix: Vec<Vec<i32>> = (0..rows)
+                .map(|_| (0..cols).map(|_| (random.below(5) as i32) - 2).collect())
+                .collect();
+            for tie_break in [TieBreak::Shortest, TieBreak::Longest] {
+                for empty in [EmptySubarray::Disallow, EmptySubarray::Allow] {
+                    let finder = MaxSubarrayFinder::new().tie_break(tie_break).empty(empty);
+                    let expected = brute_force(&finder, &matrix);
+                    assert_eq!(finder.submatrix(&matrix)?, expected, "{:?}", matrix);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_transpose_tall_matrices() -> Result<(), &'static str> {
+        // 行比列多得多，走转置的分支，答案要和不转置的暴力枚举一致
+        let tall: Vec<Vec<i32>> = vec![
+            vec![-1, 2],
+            vec![3, -4],
+            vec![5, 6],
+            vec![-7, 8],
+            vec![9, -10]
+        ];
+        let expected = MaxSubmatrix { rows: 2..4, cols: 1..2, sum: 14 };
+        assert_eq!(max_submatrix(&tall)?, Some(expected));
+
+        let mut random = XorShift64::new(4949);
+        for _ in 0..100 {
+            let cols = random.below(4);
+            let rows = cols + 1 + random.below(12);
+            let matrix: Vec<Vec<i32>> = (0..rows)
+                .map(|_| (0..cols).map(|_| (random.below(5) as i32) - 2).collect())
+                .collect();
+            for tie_break in [TieBreak::Shortest, TieBreak::Longest] {
+                for empty in [EmptySubarray::Disallow, EmptySubarray::Allow] {
+                    let finder = MaxSubarrayFinder::new().tie_break(tie_break).empty(empty);
+                    let expected = brute_force(&finder, &matrix);
+                    assert_eq!(finder.submatrix(&matrix)?, expected, "{:?}", matrix);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_handle_degenerate_matrices() {
+        assert_eq!(max_submatrix::<i64>(&[]), Ok(None));
+        assert_eq!(max_submatrix::<i64>(&[vec![], vec![]]), Ok(None));
+        let finder = MaxSubarrayFinder::new().empty(EmptySubarray::Allow);
+        let negative = vec![vec![-1i8, -2], vec![-3, -4]];
+        let empty = MaxSubmatrix { rows: 0..0, cols: 0..0, sum: 0 };
+        assert_eq!(finder.submatrix(&negative), Ok(Some(empty)));
+        assert_eq!(max_submatrix(&[vec![1, 2], vec![3]]), Err("Rows have different lengths"));
+    }
+}
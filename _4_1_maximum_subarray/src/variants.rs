@@ -0,0 +1,471 @@
+use std::{ collections::VecDeque, ops::{ Range, RangeInclusive } };
+
+use crate::max_subarray::{
+    EmptySubarray,
+    ExactSubarraySum,
+    MaxSubarray,
+    MaxSubarrayFinder,
+    SubarraySum,
+    TieBreak,
+};
+
+// 最大子数组的几种变形，结果都用MaxSubarray表示
+// 和kadane一样，和相同时按TieBreak比长度，长度也相同时取最靠左的，允许空子数组时也和它比较
+// 后面同名的函数用默认选项（最短、不允许空子数组）
+
+impl MaxSubarrayFinder {
+    // 环形数组的最大子数组，首尾相接，子数组可以跨过末尾回到开头，range的表示见MaxSubarray
+    // 跨过末尾的子数组是后缀slice[s..]加前缀slice[..e]，e < s，中间至少空出一个元素，
+    // e == s时就是整个数组，已经算在不跨过末尾的子数组里了
+    // s从左往右，前缀和随着累加，同时记下[1, s)里最好的e；后缀和事先从右往左累加好，O(n)
+    // 这样每个子数组的和都只由它自己的元素加出来，不用总和减去中间最小的一段，
+    // 浮点数时后者会把中间那一段的舍入误差带进结果
+    pub fn circular<T: SubarraySum>(&self, slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+        let n = slice.len();
+        let mut best = self.empty(EmptySubarray::Disallow).kadane(slice);
+
+        // suffix[s]为slice[s..]的和
+        let mut suffix = vec![T::zero(); n + 1];
+        for s in (0..n).rev() {
+            suffix[s] = T::add(slice[s].widen(), suffix[s + 1]);
+        }
+
+        // 起点都是s，和相同时按TieBreak取最短（e最小）或最长（e最大）的
+        let longest = self.tie_break == TieBreak::Longest;
+        let mut prefix = T::zero();
+        let mut best_prefix: Option<(T::Sum, usize)> = None;
+        for (s, &suffix_sum) in suffix.iter().enumerate().take(n).skip(2) {
+            let e = s - 1;
+            prefix = T::add(prefix, slice[e - 1].widen());
+            if best_prefix.is_none_or(|(sum, _)| prefix > sum || (longest && prefix == sum)) {
+                best_prefix = Some((prefix, e));
+            }
+            let (prefix_sum, e) = best_prefix.unwrap();
+            let wrapped = MaxSubarray { range: s..e + n, sum: T::add(suffix_sum, prefix_sum) };
+            if best.as_ref().is_none_or(|best| self.better(&wrapped, best)) {
+                best = Some(wrapped);
+            }
+        }
+        self.finish(best, T::zero())
+    }
+
+    // 长度在lengths之内的最大子数组，长度为0的下界按1算，没有满足条件的子数组时为None
+    // lengths包含0时空子数组也满足长度的条件，是否参与比较由EmptySubarray决定
+    // 以end结尾的子数组和为prefix[end] - prefix[start]，start在[end - U, end - L]之间，
+    // 求这个窗口里最小的前缀和，用单调队列维护，每个start进出队列各一次，O(n)
+    // 前缀和相减只对整数是精确的，所以元素只能是整数，见ExactSubarraySum
+    pub fn bounded<T: ExactSubarraySum>(
+        &self,
+        slice: &[T],
+        lengths: RangeInclusive<usize>
+    ) -> Option<MaxSubarray<T::Sum>> {
+        let n = slice.len();
+        let min_len = (*lengths.start()).max(1);
+        let max_len = (*lengths.end()).min(n);
+        let best = if min_len <= max_len {
+            self.bounded_nonempty(slice, min_len, max_len)
+        } else {
+            None
+        };
+        if lengths.contains(&0) {
+            self.finish(best, T::zero())
+        } else {
+            best
+        }
+    }
+
+    // 1 <= min_len <= max_len <= n
+    fn bounded_nonempty<T: ExactSubarraySum>(
+        &self,
+        slice: &[T],
+        min_len: usize,
+        max_len: usize
+    ) -> Option<MaxSubarray<T::Sum>> {
+        // prefix[i]为前i个元素的和
+        let mut prefix = Vec::with_capacity(slice.len() + 1);
+        prefix.push(T::zero());
+        for &e in slice {
+            prefix.push(T::add(prefix[prefix.len() - 1], e.widen()));
+        }
+
+        // 同一个end的候选里前缀和越小越好，相等时最短取靠后的start，最长取靠前的
+        // 队列里的start从前往后，前缀和递增（最长时可以相等），队首就是窗口里最好的
+        let longest = self.tie_break == TieBreak::Longest;
+        let mut starts: VecDeque<usize> = VecDeque::new();
+        let mut best: Option<MaxSubarray<T::Sum>> = None;
+        for end in min_len..=slice.len() {
+            let start = end - min_len;
+            let dominated = |i: usize| {
+                prefix[i] > prefix[start] || (!longest && prefix[i] == prefix[start])
+            };
+            while starts.back().is_some_and(|&i| dominated(i)) {
+                starts.pop_back();
+            }
+            starts.push_back(start);
+            while starts.front().is_some_and(|&i| i + max_len < end) {
+                starts.pop_front();
+            }
+            let i = *starts.front().unwrap();
+            let candidate = MaxSubarray { range: i..end, sum: T::sub(prefix[end], prefix[i]) };
+            if best.as_ref().is_none_or(|best| self.better(&candidate, best)) {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+
+    // 允许从子数组里删去至多k个负数元素，删去以后和最大的子数组，至少留下一个元素
+    // range是删去以前的子数组，按它的长度比较，sum是删去以后的和
+    // states[r]是以当前元素结尾、至多删去r个元素时最好的和及其起点，每个元素有四种来源：
+    // 只有它自己；接在states[r]后面；它是负数，删去它，沿用上一个元素的states[r - 1]；
+    // 删去它前面连续的几个负数，从删去的第一个开始，和它自己相同，但更长
+    // 以同一个元素结尾的候选长度只由起点决定，和相同时最短取靠后的起点，最长取靠前的，O(nk)
+    pub fn with_removals<T: SubarraySum>(
+        &self,
+        slice: &[T],
+        k: usize
+    ) -> Option<MaxSubarray<T::Sum>> {
+        // 删的个数不会超过元素个数，k再大也只要这么多个状态
+        let k = k.min(slice.len());
+        let zero = T::zero();
+        let longest = self.tie_break == TieBreak::Longest;
+        let better = |a: (T::Sum, usize), b: (T::Sum, usize)| {
+            a.0 > b.0 || (a.0 == b.0 && (if longest { a.1 < b.1 } else { a.1 > b.1 }))
+        };
+
+        let mut states: Vec<Option<(T::Sum, usize)>> = vec![None; k + 1];
+        let mut negatives = 0; // 上一个元素结尾的连续负数的个数
+        let mut best: Option<MaxSubarray<T::Sum>> = None;
+        for (j, &e) in slice.iter().enumerate() {
+            let e = e.widen();
+            // r从大到小，更新states[r]时states[r - 1]还是上一个元素的
+            for r in (0..=k).rev() {
+                let mut next = (e, j);
+                if r > 0 && negatives > 0 {
+                    let candidate = (e, j - negatives.min(r));
+                    if better(candidate, next) {
+                        next = candidate;
+                    }
+                }
+                if let Some((sum, start)) = states[r] {
+                    let candidate = (T::add(sum, e), start);
+                    if better(candidate, next) {
+                        next = candidate;
+                    }
+                }
+                if r > 0 && e < zero {
+                    if let Some(candidate) = states[r - 1] {
+                        if better(candidate, next) {
+                            next = candidate;
+                        }
+                    }
+                }
+                states[r] = Some(next);
+            }
+            negatives = if e < zero { negatives + 1 } else { 0 };
+
+            let (sum, start) = states[k].unwrap();
+            let candidate = MaxSubarray { range: start..j + 1, sum };
+            if best.as_ref().is_none_or(|best| self.better(&candidate, best)) {
+                best = Some(candidate);
+            }
+        }
+        self.finish(best, zero)
+    }
+
+    // 贪心地依次取k个互不重叠的子数组：先取整个数组的最大子数组，再在剩下的部分里取最大的，以此类推
+    // 这不是和最大的k个子数组（它们可以重叠），也不一定是总和最大的k个互不重叠的子数组，
+    // 比如[3, -1, 3]取2个：贪心先取整个数组，和为5，剩下没有元素了；
+    // 和最大的两个子数组是5和3，总和最大的两个互不重叠的子数组是3和3
+    // 剩下的部分是若干段，每段缓存自己的最大子数组，取走一个以后它所在的段分成左右两段，各算一次kadane
+    // O(k(n + k))，按取出的顺序返回，和不增；元素不够分时少于k个
+    // 允许空子数组时，剩下最好的一个也不如空子数组就停止，空子数组本身不返回
+    pub fn greedy_top_k<T: SubarraySum>(
+        &self,
+        slice: &[T],
+        k: usize
+    ) -> Vec<MaxSubarray<T::Sum>> {
+        let finder = self.empty(EmptySubarray::Disallow);
+        let best_in = |segment: Range<usize>| {
+            finder.kadane(&slice[segment.clone()]).map(|found| MaxSubarray {
+                range: found.range.start + segment.start..found.range.end + segment.start,
+                sum: found.sum,
+            })
+        };
+
+        let mut segments: Vec<(Range<usize>, MaxSubarray<T::Sum>)> = vec![];
+        if let Some(found) = best_in(0..slice.len()) {
+            segments.push((0..slice.len(), found));
+        }
+        let empty = MaxSubarray { range: 0..0, sum: T::zero() };
+        let mut result = Vec::with_capacity(k.min(slice.len()));
+        while result.len() < k && !segments.is_empty() {
+            let mut index = 0;
+            for i in 1..segments.len() {
+                if self.better(&segments[i].1, &segments[index].1) {
+                    index = i;
+                }
+            }
+            if self.empty == EmptySubarray::Allow && self.better(&empty, &segments[index].1) {
+                break;
+            }
+            let (segment, found) = segments.swap_remove(index);
+            for part in [segment.start..found.range.start, found.range.end..segment.end] {
+                if let Some(part_best) = best_in(part.clone()) {
+                    segments.push((part, part_best));
+                }
+            }
+            result.push(found);
+        }
+        result
+    }
+}
+
+// 默认选项的环形最大子数组
+pub fn circular_max_subarray<T: SubarraySum>(slice: &[T]) -> Option<MaxSubarray<T::Sum>> {
+    MaxSubarrayFinder::new().circular(slice)
+}
+
+// 默认选项的长度受限的最大子数组
+pub fn bounded_max_subarray<T: ExactSubarraySum>(
+    slice: &[T],
+    lengths: RangeInclusive<usize>
+) -> Option<MaxSubarray<T::Sum>> {
+    MaxSubarrayFinder::new().bounded(slice, lengths)
+}
+
+// 默认选项的删去至多k个负数的最大子数组
+pub fn max_subarray_with_removals<T: SubarraySum>(
+    slice: &[T],
+    k: usize
+) -> Option<MaxSubarray<T::Sum>> {
+    MaxSubarrayFinder::new().with_removals(slice, k)
+}
+
+// 默认选项的贪心地依次取k个互不重叠的最大子数组
+pub fn greedy_top_k_max_subarrays<T: SubarraySum>(
+    slice: &[T],
+    k: usize
+) -> Vec<MaxSubarray<T::Sum>> {
+    MaxSubarrayFinder::new().greedy_top_k(slice, k)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::random::XorShift64;
+
+    fn random_vec(random: &mut XorShift64, max_len: usize) -> Vec<i32> {
+        let len = random.below(max_len + 1);
+        (0..len).map(|_| (random.below(9) as i32) - 4).collect()
+    }
+
+    fn sum_of(slice: &[i32], range: Range<usize>) -> i128 {
+        range.map(|i| slice[i % slice.len()] as i128).sum()
+    }
+
+    fn finders() -> Vec<MaxSubarrayFinder> {
+        let mut finders = vec![];
+        for tie_break in [TieBreak::Shortest, TieBreak::Longest] {
+            for empty in [EmptySubarray::Disallow, EmptySubarray::Allow] {
+                finders.push(MaxSubarrayFinder::new().tie_break(tie_break).empty(empty));
+            }
+        }
+        finders
+    }
+
+    // 按finder的规则从所有非空的候选里选，不和空子数组比较
+    fn pick<I>(finder: &MaxSubarrayFinder, candidates: I) -> Option<MaxSubarray<i128>>
+        where I: Iterator<Item = MaxSubarray<i128>>
+    {
+        let mut best: Option<MaxSubarray<i128>> = None;
+        for candidate in candidates {
+            if best.as_ref().is_none_or(|best| finder.better(&candidate, best)) {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn it_find_circular() {
+        let week = [5i32, -3, -4, -2, 1, 3, 4];
+        let result = circular_max_subarray(&week).unwrap();
+        // 周五到下周一
+        assert_eq!(result, MaxSubarray { range: 4..8, sum: 13 });
+
+        // 总和减去中间一段时，-1e17吃掉了3和2，结果是0
+        let readings = [3.0f64, -1e17, 2.0];
+        assert_eq!(circular_max_subarray(&readings), Some(MaxSubarray { range: 2..4, sum: 5.0 }));
+
+        let mut random = XorShift64::new(50);
+        for _ in 0..500 {
+            let vec = random_vec(&mut random, 12);
+            let n = vec.len();
+            for finder in finders() {
+                let ranges = (0..n).flat_map(|start| {
+                    (start + 1..=start + n).map(move |end| start..end)
+                });
+                let candidates = ranges.map(|range| MaxSubarray {
+                    sum: sum_of(&vec, range.clone()),
+                    range,
+                });
+                let expected = finder.finish(pick(&finder, candidates), 0);
+                assert_eq!(finder.circular(&vec), expected, "{:?} {:?}", finder, vec);
+            }
+        }
+    }
+
+    #[test]
+    fn it_find_bounded() {
+        let vec = [3i32, -1, -1, 4, -10, 2, 2];
+        assert_eq!(bounded_max_subarray(&vec, 1..=7), Some(MaxSubarray { range: 0..4, sum: 5 }));
+        assert_eq!(bounded_max_subarray(&vec, 1..=2), Some(MaxSubarray { range: 3..4, sum: 4 }));
+        assert_eq!(bounded_max_subarray(&vec, 5..=5).map(|found| found.sum), Some(-3));
+        assert_eq!(bounded_max_subarray(&vec, 8..=9), None);
+        assert_eq!(bounded_max_subarray(&vec, RangeInclusive::new(3, 2)), None);
+        let finder = MaxSubarrayFinder::new().empty(EmptySubarray::Allow);
+        let empty = Some(MaxSubarray { range: 0..0, sum: 0 });
+        assert_eq!(finder.bounded(&[-1i64, -2], 0..=1), empty);
+        assert_eq!(finder.bounded(&[-1i64, -2], 1..=1), Some(MaxSubarray { range: 0..1, sum: -1 }));
+
+        let mut random = XorShift64::new(51);
+        for _ in 0..500 {
+            let vec = random_vec(&mut random, 12);
+            let low = random.below(6);
+            let high = low + random.below(6);
+            let n = vec.len();
+            for finder in finders() {
+                let ranges = (0..n).flat_map(|start| {
+                    (start + low.max(1)..=(start + high).min(n)).map(move |end| start..end)
+                });
+                let candidates = ranges.map(|range| MaxSubarray {
+                    sum: sum_of(&vec, range.clone()),
+                    range,
+                });
+                let mut expected = pick(&finder, candidates);
+                if low == 0 {
+                    expected = finder.finish(expected, 0);
+                }
+                let result = finder.bounded(&vec, low..=high);
+                assert_eq!(result, expected, "{:?} {:?} {}..={}", finder, vec, low, high);
+            }
+        }
+    }
+
+    #[test]
+    fn it_remove_negatives() {
+        let vec = [4i32, -6, 3, -1, -2, 5, -9, 1];
+        assert_eq!(max_subarray_with_removals(&vec, 0), Some(MaxSubarray { range: 5..6, sum: 5 }));
+        assert_eq!(max_subarray_with_removals(&vec, 1), Some(MaxSubarray { range: 0..6, sum: 9 }));
+        assert_eq!(max_subarray_with_removals(&vec, 3).map(|found| found.sum), Some(12));
+        let single = MaxSubarray { range: 1..2, sum: -1 };
+        assert_eq!(max_subarray_with_removals(&[-3i32, -1], 5), Some(single.clone()));
+        // k比元素个数多时按元素个数算，不会按k分配状态
+        assert_eq!(max_subarray_with_removals(&[-3i32, -1], usize::MAX), Some(single));
+        assert_eq!(max_subarray_with_removals::<i32>(&[], 2), None);
+        // 最长时把开头删去的负数也算进来
+        let finder = MaxSubarrayFinder::new().tie_break(TieBreak::Longest);
+        let longest = Some(MaxSubarray { range: 0..3, sum: 2 });
+        assert_eq!(finder.with_removals(&[-1i32, -2, 2], 2), longest);
+
+        // 暴力：每一段删去至多k个最小的负数，至少留下一个
+        let mut random = XorShift64::new(52);
+        for _ in 0..300 {
+            let vec = random_vec(&mut random, 10);
+            let k = random.below(4);
+            let removed_sum = |range: Range<usize>| {
+                let mut elems = vec[range].to_vec();
+                elems.sort();
+                let removable = elems
+                    .iter()
+                    .take(elems.len() - 1)
+                    .take_while(|&&e| e < 0)
+                    .count()
+                    .min(k);
+                elems[removable..].iter().map(|&e| e as i128).sum::<i128>()
+            };
+            for finder in finders() {
+                let ranges = (0..vec.len()).flat_map(|start| {
+                    (start + 1..=vec.len()).map(move |end| start..end)
+                });
+                let candidates = ranges.map(|range| MaxSubarray {
+                    sum: removed_sum(range.clone()),
+                    range,
+                });
+                let expected = finder.finish(pick(&finder, candidates), 0);
+                let result = finder.with_removals(&vec, k);
+                assert_eq!(result, expected, "{:?} {:?} {}", finder, vec, k);
+            }
+        }
+    }
+
+    #[test]
+    fn it_take_greedy_top_k() {
+        let vec = [2i32, -5, 4, -1, 3, -8, 6, -2, 1];
+        let result = greedy_top_k_max_subarrays(&vec, 3);
+        assert_eq!(
+            result,
+            vec![
+                MaxSubarray {
+                    range: 6..7,
+                    sum: 6,
+                },
+                MaxSubarray {
+                    range: 2..5,
+                    sum: 6,
+                },
+                MaxSubarray {
+                    range: 0..1,
+                    sum: 2,
+                }
+            ]
+        );
+        assert_eq!(greedy_top_k_max_subarrays(&[1i32, 2], 5).len(), 1);
+        assert_eq!(greedy_top_k_max_subarrays(&[-1i32, -2], 5).len(), 2);
+        let finder = MaxSubarrayFinder::new().empty(EmptySubarray::Allow);
+        assert_eq!(
+            finder.greedy_top_k(&[-1i32, 3, -2], 5),
+            vec![MaxSubarray { range: 1..2, sum: 3 }]
+        );
+
+        // 贪心不是和最大的k个子数组：[3, -1, 3]里和最大的两个是0..3和0..1（或2..3），和为5和3，
+        // 总和最大的两个互不重叠的子数组是0..1和2..3，共6；贪心取了0..3以后就没有元素了
+        assert_eq!(
+            greedy_top_k_max_subarrays(&[3i32, -1, 3], 2),
+            vec![MaxSubarray { range: 0..3, sum: 5 }]
+        );
+
+        // 暴力：每次在没被取走的元素里枚举
+        let mut random = XorShift64::new(53);
+        for _ in 0..300 {
+            let vec = random_vec(&mut random, 12);
+            let k = random.below(5);
+            for finder in finders() {
+                let mut taken = vec![false; vec.len()];
+                let mut expected = vec![];
+                while expected.len() < k {
+                    let ranges = (0..vec.len()).flat_map(|start| {
+                        (start + 1..=vec.len()).map(move |end| start..end)
+                    });
+                    let candidates = ranges
+                        .filter(|range| !taken[range.clone()].iter().any(|&t| t))
+                        .map(|range| MaxSubarray {
+                            sum: sum_of(&vec, range.clone()),
+                            range,
+                        });
+                    let Some(best) = pick(&finder, candidates) else {
+                        break;
+                    };
+                    if finder.finish(Some(best.clone()), 0) != Some(best.clone()) {
+                        break;
+                    }
+                    taken[best.range.clone()].fill(true);
+                    expected.push(best);
+                }
+                let result = finder.greedy_top_k(&vec, k);
+                assert_eq!(result, expected, "{:?} {:?} {}", finder, vec, k);
+            }
+        }
+    }
+}
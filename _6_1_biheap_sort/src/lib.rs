@@ -1,12 +1,32 @@
-use std::ptr;
+use algorithms_prelude::{ CompareSorter, VecSorter };
 
-use algorithms_prelude::CompareSorter;
+mod bottom_up_heap_sort;
+mod smooth_sort;
+mod weak_heap_sort;
 
-pub struct BiheapSorter<'a, Seq>(pub &'a mut Seq);
+pub use bottom_up_heap_sort::BottomUpHeapSorter;
+pub use smooth_sort::SmoothSorter;
+pub use weak_heap_sort::WeakHeapSorter;
+
+pub struct BiheapSorter<'a, Seq: ?Sized>(pub &'a mut Seq);
 
 impl<'a, Elem> CompareSorter for BiheapSorter<'a, Vec<Elem>> {
     type Element = Elem;
 
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        BiheapSorter(self.0.as_mut_slice()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for BiheapSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        BiheapSorter(vec).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> CompareSorter for BiheapSorter<'a, [Elem]> {
+    type Element = Elem;
+
     fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
         let vec = &mut self.0;
 
@@ -14,51 +34,131 @@ impl<'a, Elem> CompareSorter for BiheapSorter<'a, Vec<Elem>> {
             return;
         }
 
-        build_max_heap(vec, compare);
-        for i in (1..vec.len()).rev() {
-            unsafe {
-                ptr::swap_nonoverlapping(&mut vec[0], &mut vec[i], 1);
-            }
-            max_heapify(vec, compare, 0, i);
-        }
+        make_heap(vec, compare);
+        sort_heap(vec, compare);
     }
 }
 
-// 构建最大堆，这个只会执行一次
+// 以下是切片上的堆操作，可以在自己的缓冲区里维护一个堆，不需要套一层容器
+// compare的含义和sort_by相同：堆顶是排序后应当排在最后的元素
+// 比如传入prev < next，就是最大堆
+// 下标从0开始，i的左孩子是2i + 1，右孩子是2i + 2，父节点是(i - 1) / 2
+
+// 构建堆
 // 构建后，父节点都会大于左右节点，而左右节点之间的大小未定
-// i向下取整，因为i不是一个右开区间的右界，而是指向具体下标的“指针”
-fn build_max_heap<T>(vec: &mut Vec<T>, compare: fn(prev: &T, next: &T) -> bool) {
-    for i in (0..vec.len() >> 1).rev() {
-        max_heapify(vec, compare, i, vec.len());
+// 从最后一个非叶子节点往前逐个下沉，叶子本身就是堆
+pub fn make_heap<T>(slice: &mut [T], compare: fn(prev: &T, next: &T) -> bool) {
+    let heap_size = slice.len();
+    for i in (0..heap_size >> 1).rev() {
+        sift_down(slice, compare, i, heap_size);
     }
 }
 
-fn max_heapify<T>(
-    vec: &mut Vec<T>,
+// slice[..len - 1]是堆，把最后一个元素上浮，使整个slice成为堆
+pub fn push_heap<T>(slice: &mut [T], compare: fn(prev: &T, next: &T) -> bool) {
+    if slice.is_empty() {
+        return;
+    }
+    let mut i = slice.len() - 1;
+    while i > 0 {
+        let parent = ((i + 1) >> 1) - 1;
+        if compare(&slice[parent], &slice[i]) {
+            slice.swap(parent, i);
+            i = parent;
+        } else {
+            break;
+        }
+    }
+}
+
+// slice是堆，把堆顶换到最后，剩下的slice[..len - 1]重新成为堆
+pub fn pop_heap<T>(slice: &mut [T], compare: fn(prev: &T, next: &T) -> bool) {
+    let heap_size = slice.len();
+    if heap_size < 2 {
+        return;
+    }
+    slice.swap(0, heap_size - 1);
+    sift_down(slice, compare, 0, heap_size - 1);
+}
+
+// slice是堆，不断pop_heap，结果按compare排好序
+pub fn sort_heap<T>(slice: &mut [T], compare: fn(prev: &T, next: &T) -> bool) {
+    for heap_size in (2..=slice.len()).rev() {
+        pop_heap(&mut slice[..heap_size], compare);
+    }
+}
+
+pub fn is_heap<T>(slice: &[T], compare: fn(prev: &T, next: &T) -> bool) -> bool {
+    is_heap_until(slice, compare) == slice.len()
+}
+
+// 返回最长的堆前缀的长度
+// 父节点严格排在孩子之前才算违反，所以compare是否包含等于都不影响结果
+pub fn is_heap_until<T>(slice: &[T], compare: fn(prev: &T, next: &T) -> bool) -> usize {
+    for i in 1..slice.len() {
+        let parent = ((i + 1) >> 1) - 1;
+        if compare(&slice[parent], &slice[i]) && !compare(&slice[i], &slice[parent]) {
+            return i;
+        }
+    }
+    slice.len()
+}
+
+// 下沉，书中是尾递归，这里写成循环，只用O(1)的栈空间
+fn sift_down<T>(
+    slice: &mut [T],
     compare: fn(prev: &T, next: &T) -> bool,
-    i: usize,
+    mut i: usize,
     heap_size: usize
 ) {
-    let l = ((i + 1) << 1) - 1; // 转换成1开头下标，乘以2后再转换成0开头下标
-    let r = (i + 1) << 1; // 就在右边
-    let mut largest = i;
-    if l < heap_size && compare(&vec[largest], &vec[l]) {
-        largest = l;
-    }
-    if r < heap_size && compare(&vec[largest], &vec[r]) {
-        largest = r;
-    }
-    if largest != i {
-        unsafe {
-            ptr::swap_nonoverlapping(&mut vec[i], &mut vec[largest], 1);
+    loop {
+        let l = ((i + 1) << 1) - 1; // 转换成1开头下标，乘以2后再转换成0开头下标
+        let r = (i + 1) << 1; // 就在右边
+        let mut largest = i;
+        if l < heap_size && compare(&slice[largest], &slice[l]) {
+            largest = l;
+        }
+        if r < heap_size && compare(&slice[largest], &slice[r]) {
+            largest = r;
         }
-        max_heapify(vec, compare, largest, heap_size);
+        if largest == i {
+            break;
+        }
+        slice.swap(i, largest);
+        i = largest;
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use algorithms_prelude::permutation;
+    use algorithms_prelude::random::XorShift64;
+    use algorithms_prelude::stack::{ run_with_stack, SMALL_STACK };
+    use std::cell::Cell;
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn counting_lt(prev: &u32, next: &u32) -> bool {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        prev < next
+    }
+
+    // compare只能是函数指针，不能捕获计数器，所以用线程局部变量计数
+    pub(crate) fn count_comparisons(sort: impl FnOnce(fn(&u32, &u32) -> bool)) -> usize {
+        COMPARISONS.with(|c| c.set(0));
+        sort(counting_lt);
+        COMPARISONS.with(|c| c.get())
+    }
+
+    // 0..len的一个固定的乱序排列
+    pub(crate) fn shuffled(len: usize) -> Vec<u32> {
+        let mut v: Vec<u32> = (0..len as u32).collect();
+        XorShift64::new(0x2545_f491_4f6c_dd1d).shuffle(&mut v);
+        v
+    }
     #[test]
     fn it_sort_ascending() {
         let mut v = vec![22, 43, 145, 1, 9];
@@ -235,4 +335,111 @@ mod test {
 
         assert_eq!(v, sorted_v);
     }
+
+    #[test]
+    fn it_heap_primitives() {
+        let compare = |prev: &i32, next: &i32| prev < next;
+        let mut v = vec![22, 43, 145, 1, 9, 43, 0];
+        assert!(!is_heap(&v, compare));
+        assert_eq!(is_heap_until(&v, compare), 1);
+
+        make_heap(&mut v, compare);
+        assert!(is_heap(&v, compare));
+        assert_eq!(v[0], 145);
+
+        // 在缓冲区末尾追加再上浮
+        v.push(200);
+        push_heap(&mut v, compare);
+        assert!(is_heap(&v, compare));
+        assert_eq!(v[0], 200);
+
+        pop_heap(&mut v, compare);
+        assert_eq!(v.pop(), Some(200));
+        assert!(is_heap(&v, compare));
+
+        sort_heap(&mut v, compare);
+        assert_eq!(v, vec![0, 1, 9, 22, 43, 43, 145]);
+    }
+
+    #[test]
+    fn it_heap_primitives_min_heap() {
+        let compare = |prev: &i32, next: &i32| prev >= next;
+        let mut v = vec![];
+        for e in [5, 3, 3, 8, 1] {
+            v.push(e);
+            push_heap(&mut v, compare);
+            assert!(is_heap(&v, compare));
+        }
+        assert_eq!(v[0], 1);
+        let mut popped = vec![];
+        while !v.is_empty() {
+            pop_heap(&mut v, compare);
+            popped.push(v.pop().unwrap());
+        }
+        assert_eq!(popped, vec![1, 3, 3, 5, 8]);
+    }
+
+    #[test]
+    fn it_comparison_count() {
+        // 普通的下沉每层比较两次，n = 4096的随机输入实测85611次，约1.74nlgn
+        // 作为其他堆排序的对照
+        let mut v = shuffled(4096);
+        let count = count_comparisons(|compare| BiheapSorter(&mut v).sort_by(compare));
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+        assert!(count > 4096 * 12 * 3 / 2, "comparisons: {}", count);
+    }
+
+    // 已排序的输入，建堆时每个元素都要沉到底
+    // 平滑排序在有序的输入上是O(n)，所以让它排成降序，对它来说输入是逆序的
+    fn sort_adversarial_with_small_stack(len: u32) {
+        let ascending: Vec<u32> = (0..len).collect();
+        let descending: Vec<u32> = (0..len).rev().collect();
+        let sorters: [fn(&mut Vec<u32>); 4] = [
+            |v| BiheapSorter(v).sort_by(|prev, next| prev < next),
+            |v| BottomUpHeapSorter(v).sort_by(|prev, next| prev < next),
+            |v| WeakHeapSorter(v).sort_by(|prev, next| prev < next),
+            |v| SmoothSorter(v).sort_by(|prev, next| prev > next),
+        ];
+        let expected = [&ascending, &ascending, &ascending, &descending];
+        for (sort, expected) in sorters.into_iter().zip(expected) {
+            let mut v = ascending.clone();
+            run_with_stack(SMALL_STACK, || sort(&mut v));
+            assert_eq!(&v, expected);
+        }
+    }
+
+    #[test]
+    fn it_sort_adversarial_with_small_stack() {
+        sort_adversarial_with_small_stack(100_000);
+    }
+
+    #[test]
+    #[ignore = "10^7个元素，用cargo test --release -- --ignored运行"]
+    fn it_sort_ten_million_with_small_stack() {
+        sort_adversarial_with_small_stack(10_000_000);
+    }
+
+    #[test]
+    fn it_arg_sort() {
+        let v = shuffled(1000);
+        let compare = |prev: &u32, next: &u32| prev < next;
+        let perms = [
+            permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                BiheapSorter(indexed).sort_by(compare)
+            }),
+            permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                BottomUpHeapSorter(indexed).sort_by(compare)
+            }),
+            permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                WeakHeapSorter(indexed).sort_by(compare)
+            }),
+            permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                SmoothSorter(indexed).sort_by(compare)
+            }),
+        ];
+        for perm in perms {
+            // 键互不相同，排列是唯一的，就是每个值所在的位置
+            assert!(perm.iter().enumerate().all(|(i, &p)| v[p] == i as u32));
+        }
+    }
 }
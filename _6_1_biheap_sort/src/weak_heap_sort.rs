@@ -0,0 +1,157 @@
+use algorithms_prelude::{ CompareSorter, VecSorter };
+
+// 弱堆排序（Dutton）
+// 弱堆放宽了堆的条件：节点只需要不小于它右子树中的所有节点，左子树不做要求，根没有左子树
+// 每个节点有一个翻转位r，r[x]为真时交换x的左右孩子，这样“交换两棵子树”只需要翻转一位
+// x的左孩子是2x + r[x]，右孩子是2x + 1 - r[x]
+//
+// 节点j的“特殊祖先”是沿着父节点往上，第一个使j位于其右子树中的祖先，
+// 弱堆条件等价于：每个节点的特殊祖先都不小于它
+// join(i, j)：i是j的特殊祖先，若j更大则交换并翻转r[j]，这样仍满足弱堆条件，只比较一次
+//
+// 比较次数最多为(n - 1)⌈lgn⌉ - 2^⌈lgn⌉ + n - 1，约nlgn + 0.1n，接近比较排序的下界lg(n!)
+// 额外空间是n个二进制位，按位压在u64里，共⌈n / 64⌉个字
+pub struct WeakHeapSorter<'a, Seq: ?Sized>(pub &'a mut Seq);
+
+impl<'a, Elem> CompareSorter for WeakHeapSorter<'a, Vec<Elem>> {
+    type Element = Elem;
+
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        WeakHeapSorter(self.0.as_mut_slice()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for WeakHeapSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        WeakHeapSorter(vec).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> CompareSorter for WeakHeapSorter<'a, [Elem]> {
+    type Element = Elem;
+
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        let vec = &mut self.0;
+        let len = vec.len();
+
+        if len < 2 {
+            return;
+        }
+
+        let mut r = ReverseBits::new(len);
+
+        // 建堆：从后往前，每个节点和它的特殊祖先join一次，共n - 1次比较
+        for j in (1..len).rev() {
+            let i = distinguished_ancestor(&r, j);
+            join(vec, &mut r, compare, i, j);
+        }
+
+        for heap_size in (2..len).rev() {
+            vec.swap(0, heap_size);
+            // 从根的右孩子出发，一路走左孩子到底
+            let mut x = 1;
+            loop {
+                let y = (x << 1) + r.get(x);
+                if y >= heap_size {
+                    break;
+                }
+                x = y;
+            }
+            // 再沿路径往回，每个节点都以根为特殊祖先
+            while x > 0 {
+                join(vec, &mut r, compare, 0, x);
+                x >>= 1;
+            }
+        }
+        vec.swap(0, 1);
+    }
+}
+
+// 翻转位，第i位在第i / 64个字的第i % 64位
+struct ReverseBits(Vec<u64>);
+
+impl ReverseBits {
+    fn new(len: usize) -> ReverseBits {
+        ReverseBits(vec![0; len.div_ceil(64)])
+    }
+
+    // 返回0或1，可以直接加到下标上
+    fn get(&self, i: usize) -> usize {
+        ((self.0[i >> 6] >> (i & 63)) & 1) as usize
+    }
+
+    fn flip(&mut self, i: usize) {
+        self.0[i >> 6] ^= 1 << (i & 63);
+    }
+}
+
+// j是父节点的左孩子时继续往上
+fn distinguished_ancestor(r: &ReverseBits, mut j: usize) -> usize {
+    while (j & 1) == r.get(j >> 1) {
+        j >>= 1;
+    }
+    j >> 1
+}
+
+fn join<T>(
+    slice: &mut [T],
+    r: &mut ReverseBits,
+    compare: fn(prev: &T, next: &T) -> bool,
+    i: usize,
+    j: usize
+) {
+    if compare(&slice[i], &slice[j]) {
+        slice.swap(i, j);
+        r.flip(j);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{ count_comparisons, shuffled };
+
+    #[test]
+    fn it_sort_ascending() {
+        let mut v = vec![22, 43, 145, 1, 9];
+        WeakHeapSorter(&mut v).sort_by(|prev, next| prev < next);
+        assert_eq!(v, vec![1, 9, 22, 43, 145]);
+    }
+
+    #[test]
+    fn it_sort_descending_with_duplicates() {
+        let mut v = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        WeakHeapSorter(&mut v).sort_by(|prev, next| prev >= next);
+        assert_eq!(v, vec![9, 6, 5, 5, 5, 4, 3, 3, 2, 1, 1]);
+    }
+
+    #[test]
+    fn it_sort_small() {
+        for len in 0..10 {
+            let mut v: Vec<usize> = (0..len).rev().collect();
+            WeakHeapSorter(&mut v).sort_by(|prev, next| prev < next);
+            assert_eq!(v, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn it_sort_subslice() {
+        // 只排中间一段，两头不动
+        let mut v = shuffled(200);
+        let (head, tail) = (v[..50].to_vec(), v[150..].to_vec());
+        WeakHeapSorter(&mut v[50..150]).sort_by(|prev, next| prev < next);
+        assert!(v[50..150].windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(v[..50], head[..]);
+        assert_eq!(v[150..], tail[..]);
+    }
+
+    #[test]
+    fn it_comparison_count() {
+        // n = 4096，⌈lgn⌉ = 12，上界为4095 * 12 - 4096 + 4095 = 49139
+        // lg(4096!) ≈ 43250，是任何比较排序的下界，实测47269次
+        let mut v = shuffled(4096);
+        let count = count_comparisons(|compare| WeakHeapSorter(&mut v).sort_by(compare));
+        assert!(v.windows(2).all(|w| w[0] <= w[1]));
+        assert!(count <= 49139, "comparisons: {}", count);
+    }
+}
@@ -0,0 +1,125 @@
+use std::cmp::Ordering;
+
+use algorithms_prelude::random::XorShift64;
+
+use crate::dutch_flag_partition;
+
+// 区间的模糊排序（思考题7-6）
+// 给定n个闭区间[a, b]，排列它们，使得可以从每个区间里各取一个点c，c按区间的顺序不降
+// 相当于键是不确定的快速排序：随机取一个区间作主元，求出它和其他区间的公共部分，
+// 比这部分整个靠左的放左边，整个靠右的放右边，和它相交的放中间，中间的不再递归
+//
+// 公共部分[a, b]的求法：从主元区间出发，依次和每个与当前[a, b]相交的区间取交集
+// [a, b]只会缩小，所以扫描时与之相交的区间最终都包含[a, b]，扫描时不相交的以后也不会相交，
+// 因此中间这一段的区间都包含[a, b]，都可以取a作代表点
+// 期望时间O(nlgn)；所有区间有公共点时，第一次划分就全部落在中间，只需O(n)
+pub fn fuzzy_sort<T, K, F>(slice: &mut [T], endpoints: F)
+    where K: PartialOrd + Copy, F: Fn(&T) -> (K, K)
+{
+    let mut random = XorShift64::new(FUZZY_SEED);
+    let len = slice.len();
+    fuzzy(slice, &endpoints, &mut random, 0, len);
+}
+
+// 固定的种子，同样的输入总是得到同样的结果
+const FUZZY_SEED: u64 = 76;
+
+// 同QuickSorter，只对较短的一边递归
+fn fuzzy<T, K, F>(
+    slice: &mut [T],
+    endpoints: &F,
+    random: &mut XorShift64,
+    mut first: usize,
+    mut end: usize
+)
+    where K: PartialOrd + Copy, F: Fn(&T) -> (K, K)
+{
+    while end - first > 1 {
+        let pivot = first + random.below(end - first);
+        let (mut a, mut b) = endpoints(&slice[pivot]);
+        for e in &slice[first..end] {
+            let (x, y) = endpoints(e);
+            if x <= b && y >= a {
+                if x > a {
+                    a = x;
+                }
+                if y < b {
+                    b = y;
+                }
+            }
+        }
+
+        let (left_end, right_first) = dutch_flag_partition(slice, first, end, |e| {
+            let (x, y) = endpoints(e);
+            if y < a {
+                Ordering::Less
+            } else if x > b {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        });
+        if left_end - first < end - right_first {
+            fuzzy(slice, endpoints, random, first, left_end);
+            first = right_first;
+        } else {
+            fuzzy(slice, endpoints, random, right_first, end);
+            end = left_end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    // 贪心地取代表点：每个区间取不小于上一个代表点的最小值
+    fn is_fuzzy_sorted(intervals: &[(f64, f64)]) -> bool {
+        let mut c = f64::NEG_INFINITY;
+        for &(a, b) in intervals {
+            c = c.max(a);
+            if c > b {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn it_sort_disjoint_intervals() {
+        let mut v = vec![(5.0, 6.0), (1.0, 2.0), (9.0, 9.5), (3.0, 4.0), (7.0, 8.0)];
+        fuzzy_sort(&mut v, |e| *e);
+        assert_eq!(v, vec![(1.0, 2.0), (3.0, 4.0), (5.0, 6.0), (7.0, 8.0), (9.0, 9.5)]);
+    }
+
+    #[test]
+    fn it_sort_random_intervals() {
+        let mut random = XorShift64::new(76);
+        for len in [0, 1, 2, 10, 1000] {
+            let mut v: Vec<(f64, f64)> = (0..len)
+                .map(|_| {
+                    let a = random.next_f64() * 100.0;
+                    (a, a + random.next_f64() * 5.0)
+                })
+                .collect();
+            fuzzy_sort(&mut v, |e| *e);
+            assert!(is_fuzzy_sorted(&v));
+        }
+    }
+
+    #[test]
+    fn it_sort_overlapping_intervals_in_linear_time() {
+        // 所有区间都包含50，一次划分就结束，每个区间只被取两次端点，主元多取一次
+        let mut random = XorShift64::new(77);
+        let mut v: Vec<(i64, i64)> = (0..10_000)
+            .map(|_| (50 - (random.below(50) as i64), 50 + (random.below(50) as i64)))
+            .collect();
+        let calls = Cell::new(0);
+        fuzzy_sort(&mut v, |e| {
+            calls.set(calls.get() + 1);
+            *e
+        });
+        assert_eq!(calls.get(), 2 * 10_000 + 1);
+    }
+}
@@ -0,0 +1,220 @@
+use algorithms_prelude::{ CompareSorter, VecSorter };
+use biheap_sort::BiheapSorter;
+use issort::InsertionSorter;
+
+use crate::{ partrition, Partition, Pivot, PivotChooser };
+
+// 内省排序
+// 以快速排序开始，同时记录划分的层数，超过2⌊lgn⌋层说明主元一直选得很差，
+// 剩下的区间改用堆排序，所以最坏也是O(nlgn)
+// 区间短于INSERTION_THRESHOLD时交给插入排序，小区间上插入排序的常数更小
+// 和QuickSorter一样只对较短的一边递归，栈深度O(lgn)
+// 主元用九数取中，划分用Hoare的方法，对大量重复的键也不会退化
+// 不稳定，一般情况下用它作默认的排序
+pub struct IntroSorter<'a, Seq: ?Sized>(pub &'a mut Seq);
+
+const INSERTION_THRESHOLD: usize = 16;
+
+impl<'a, Elem> CompareSorter for IntroSorter<'a, Vec<Elem>> {
+    type Element = Elem;
+
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        IntroSorter(self.0.as_mut_slice()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for IntroSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        IntroSorter(vec).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> CompareSorter for IntroSorter<'a, [Elem]> {
+    type Element = Elem;
+
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        intro_sort_slice(self.0, compare);
+    }
+}
+
+// 返回改用堆排序的区间个数
+fn intro_sort_slice<T>(slice: &mut [T], compare: fn(prev: &T, next: &T) -> bool) -> usize {
+    let len = slice.len();
+    if len < 2 {
+        return 0;
+    }
+
+    let depth_limit = 2 * (len.ilog2() as usize);
+    let mut chooser = PivotChooser::new(Pivot::Ninther);
+    intro_sort(slice, compare, &mut chooser, depth_limit, 0, len)
+}
+
+fn intro_sort<T>(
+    slice: &mut [T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    chooser: &mut PivotChooser,
+    mut depth_limit: usize,
+    mut first: usize,
+    mut end: usize
+) -> usize {
+    let mut fallbacks = 0;
+    loop {
+        if end - first <= INSERTION_THRESHOLD {
+            InsertionSorter(&mut slice[first..end]).sort_by(compare);
+            return fallbacks;
+        }
+        if depth_limit == 0 {
+            BiheapSorter(&mut slice[first..end]).sort_by(compare);
+            return fallbacks + 1;
+        }
+        depth_limit -= 1;
+
+        let (left_end, right_first) = partrition(
+            slice,
+            compare,
+            chooser,
+            Partition::Hoare,
+            first,
+            end
+        );
+        if left_end - first < end - right_first {
+            fallbacks += intro_sort(slice, compare, chooser, depth_limit, first, left_end);
+            first = right_first;
+        } else {
+            fallbacks += intro_sort(slice, compare, chooser, depth_limit, right_first, end);
+            end = left_end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use algorithms_prelude::stack::{ run_with_stack, SMALL_STACK };
+    use algorithms_prelude::random::XorShift64;
+
+    #[test]
+    fn it_sort_ascending() {
+        let mut v = vec![22, 43, 145, 1, 9];
+        IntroSorter(&mut v).sort_by(|prev, next| prev < next);
+        assert_eq!(v, vec![1, 9, 22, 43, 145]);
+    }
+
+    #[test]
+    fn it_sort_random() {
+        let mut random = XorShift64::new(5);
+        for len in [0, 1, 2, 15, 16, 17, 100, 1000, 100_000] {
+            let v: Vec<u64> = (0..len).map(|_| random.next_u64() % 1000).collect();
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut ascending = v.clone();
+            IntroSorter(&mut ascending).sort_by(|prev, next| prev < next);
+            assert_eq!(ascending, expected);
+
+            let mut descending = v;
+            IntroSorter(&mut descending).sort_by(|prev, next| prev >= next);
+            expected.reverse();
+            assert_eq!(descending, expected);
+        }
+    }
+
+    #[test]
+    fn it_struct_sort_ascending_idempotence() {
+        #[derive(Debug, PartialEq, Clone)]
+        struct Foo {
+            id: u32,
+            name: &'static str,
+        }
+
+        let compare = |prev: &Foo, next: &Foo| prev.id <= next.id;
+        let mut v: Vec<Foo> = (0..100)
+            .map(|i| Foo {
+                id: (i * 7) % 10,
+                name: "Foo",
+            })
+            .collect();
+
+        IntroSorter(&mut v).sort_by(compare);
+        let sorted_v = v.clone();
+        IntroSorter(&mut v).sort_by(compare);
+        assert_eq!(v, sorted_v);
+    }
+
+    // McIlroy的“快速排序杀手”（A Killer Adversary for Quicksort）
+    // 元素一开始都是“气体”，比所有确定了值的元素都大；两个气体比较时把其中一个凝固成下一个最小的值，
+    // 优先凝固上一次比较中出现过的气体（很可能是主元），这样主元总是剩下的元素里最小的
+    // 排序只依赖比较的结果，所以按最后凝固出的值构造输入，再排一次会走完全相同的路径
+    struct Adversary {
+        values: Vec<usize>,
+        solid: usize,
+        candidate: usize,
+    }
+
+    thread_local! {
+        static ADVERSARY: RefCell<Adversary> = const {
+            RefCell::new(Adversary { values: vec![], solid: 0, candidate: 0 })
+        };
+    }
+
+    fn adversary_precedes(prev: &usize, next: &usize) -> bool {
+        ADVERSARY.with_borrow_mut(|adversary| {
+            let gas = adversary.values.len();
+            let (x, y) = (*prev, *next);
+            if adversary.values[x] == gas && adversary.values[y] == gas {
+                let frozen = if x == adversary.candidate { x } else { y };
+                adversary.values[frozen] = adversary.solid;
+                adversary.solid += 1;
+            }
+            if adversary.values[x] == gas {
+                adversary.candidate = x;
+            } else if adversary.values[y] == gas {
+                adversary.candidate = y;
+            }
+            adversary.values[x] < adversary.values[y]
+        })
+    }
+
+    fn killer_input(len: usize) -> Vec<usize> {
+        ADVERSARY.with_borrow_mut(|adversary| {
+            *adversary = Adversary { values: vec![len; len], solid: 0, candidate: 0 };
+        });
+        let mut items: Vec<usize> = (0..len).collect();
+        intro_sort_slice(&mut items, adversary_precedes);
+        ADVERSARY.with_borrow_mut(|adversary| {
+            // 从没和别的气体比较过的元素，随便给一个更大的值
+            for value in adversary.values.iter_mut().filter(|value| **value == len) {
+                *value = adversary.solid;
+                adversary.solid += 1;
+            }
+            std::mem::take(&mut adversary.values)
+        })
+    }
+
+    #[test]
+    fn it_fall_back_to_heap_sort_on_killer_input() {
+        // 针对九数取中构造的输入，每次划分都极不均匀，层数超限后改用堆排序，依然是O(nlgn)
+        let mut v = killer_input(100_000);
+        let fallbacks = run_with_stack(SMALL_STACK, || {
+            intro_sort_slice(&mut v, |prev, next| prev < next)
+        });
+        assert!(fallbacks > 0);
+        assert_eq!(v, (0..100_000).collect::<Vec<_>>());
+
+        // 有序、逆序的输入划分都很均匀，不会用到堆排序
+        let mut sorted: Vec<u32> = (0..100_000).collect();
+        assert_eq!(intro_sort_slice(&mut sorted, |prev, next| prev < next), 0);
+        assert_eq!(intro_sort_slice(&mut sorted, |prev, next| prev > next), 0);
+        assert!(sorted.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    #[ignore = "10^7个元素，用cargo test --release -- --ignored运行"]
+    fn it_sort_ten_million_with_small_stack() {
+        let mut v: Vec<u32> = (0..10_000_000).collect();
+        v.rotate_left(1);
+        run_with_stack(SMALL_STACK, || IntroSorter(&mut v).sort_by(|prev, next| prev < next));
+        assert!(v.windows(2).all(|w| w[0] < w[1]));
+    }
+}
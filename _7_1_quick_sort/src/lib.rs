@@ -1,69 +1,360 @@
-use std::ptr;
+use std::cmp::Ordering;
 
-use algorithms_prelude::CompareSorter;
+use algorithms_prelude::{ random::XorShift64, CompareSorter, VecSorter };
 
+mod fuzzy_sort;
+mod intro_sort;
+
+pub use fuzzy_sort::fuzzy_sort;
+pub use intro_sort::IntroSorter;
+
+// 主元的选取策略
+// 总是取最后一个元素时，已排序（或逆序）的输入每次都划分成n - 1和0两部分，退化成O(n^2)
+// 三数取中（思考题7-5）和九数取中让这类输入也能均匀划分，
+// 随机主元（7.3节）让任何输入的期望时间都是O(nlgn)，种子固定，所以结果可复现
+// 三数取中仍然可以被特意构造的输入（比如有序序列循环左移一位）卡成O(n^2)，输入不可信时用随机主元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pivot {
+    Last,
+    First,
+    #[default]
+    MedianOfThree,
+    // 三组三数取中，再取三个中位数的中位数，适合大区间
+    Ninther,
+    Random(u64),
+}
+
+// 划分的方法
+// Lomuto：书中正文的方法，主元换到最后，只从左往右扫一遍
+//         相等的元素越多越不均匀，全部相等时退化成O(n^2)；
+//         主元不在最后时要先换过去，compare包含等于时会打乱有序输入里相等元素的顺序
+// Hoare：思考题7-1，两个指针从两端相向扫描，遇到等于主元的元素也会停下，
+//        所以大量相等元素时两边依然均匀，交换次数也比Lomuto少
+//        主元留在原处，两个指针停下的元素都等于主元时不交换，
+//        已经有序的区间一个元素也不会动，compare包含等于时重复排序是幂等的
+// ThreeWay：分成小于、等于、大于三段，等于的一段不再递归（思考题7-2），
+//           只有少数几种不同的键时接近线性；同样不会移动有序区间里的元素
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Partition {
+    Lomuto,
+    #[default]
+    Hoare,
+    ThreeWay,
+}
+
+// 用默认的配置排序（三数取中、Hoare划分），要换主元或划分的方法用QuickSorterWith
 pub struct QuickSorter<'a, Seq>(pub &'a mut Seq);
 
+// 快速排序的配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QuickSortConfig {
+    pub pivot: Pivot,
+    pub partition: Partition,
+}
+
+impl QuickSortConfig {
+    pub fn new() -> QuickSortConfig {
+        QuickSortConfig::default()
+    }
+
+    pub fn pivot(mut self, pivot: Pivot) -> QuickSortConfig {
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn partition(mut self, partition: Partition) -> QuickSortConfig {
+        self.partition = partition;
+        self
+    }
+}
+
+// 按给定的配置排序
+pub struct QuickSorterWith<'a, Seq>(pub &'a mut Seq, pub QuickSortConfig);
+
 impl<'a, Elem> CompareSorter for QuickSorter<'a, Vec<Elem>> {
     type Element = Elem;
 
+    fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
+        QuickSorterWith(self.0, QuickSortConfig::default()).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> VecSorter for QuickSorter<'a, Vec<Elem>> {
+    fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+        QuickSorter(vec).sort_by(compare);
+    }
+}
+
+impl<'a, Elem> CompareSorter for QuickSorterWith<'a, Vec<Elem>> {
+    type Element = Elem;
+
     fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool) {
         let vec = &mut self.0;
+        let len = vec.len();
 
-        if vec.len() < 2 {
+        if len < 2 {
             return;
         }
 
-        quick_sort(vec, compare, 0, vec.len());
+        let mut chooser = PivotChooser::new(self.1.pivot);
+        quick_sort(vec, compare, &mut chooser, self.1.partition, 0, len);
     }
 }
 
+// 对较短的一边递归，较长的一边循环（思考题7-4）
+// 每次递归的区间长度至多是上一层的一半，所以栈深度是O(lgn)，和主元选得好不好无关
 fn quick_sort<T>(
-    vec: &mut Vec<T>,
+    slice: &mut [T],
     compare: fn(prev: &T, next: &T) -> bool,
-    first: usize,
-    end: usize
+    chooser: &mut PivotChooser,
+    partition: Partition,
+    mut first: usize,
+    mut end: usize
 ) {
-    if end - first > 1 {
-        // 用相同的末尾开区间原则，避免usize在0的情况下-1（即使是safe代码，这还是会panic）
-        let divider = partrition(vec, compare, first, end);
-        quick_sort(vec, compare, first, divider);
-        quick_sort(vec, compare, divider + 1, end);
+    // 用相同的末尾开区间原则，避免usize在0的情况下-1（即使是safe代码，这还是会panic）
+    while end - first > 1 {
+        // [left_end, right_first)是已经就位的元素，两边各自递归
+        let (left_end, right_first) = partrition(slice, compare, chooser, partition, first, end);
+        if left_end - first < end - right_first {
+            quick_sort(slice, compare, chooser, partition, first, left_end);
+            first = right_first;
+        } else {
+            quick_sort(slice, compare, chooser, partition, right_first, end);
+            end = left_end;
+        }
     }
 }
 
 fn partrition<T>(
-    vec: &mut Vec<T>,
+    slice: &mut [T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    chooser: &mut PivotChooser,
+    partition: Partition,
+    first: usize,
+    end: usize
+) -> (usize, usize) {
+    let mut pivot = chooser.choose(slice, compare, first, end);
+    match partition {
+        Partition::Lomuto => {
+            slice.swap(pivot, end - 1);
+            let divider = lomuto_partition(slice, compare, first, end);
+            (divider, divider + 1)
+        }
+        Partition::Hoare => hoare_partition(slice, compare, &mut pivot, first, end),
+        Partition::ThreeWay => three_way_partition(slice, compare, pivot, first, end),
+    }
+}
+
+// 主元在最后，返回主元最终的位置
+fn lomuto_partition<T>(
+    slice: &mut [T],
     compare: fn(prev: &T, next: &T) -> bool,
     first: usize,
     end: usize
 ) -> usize {
-    // 随机选一个主元，让划分更平均，但这样强行换位置，就做不到幂等了
-    // 而且最后一个元素的大小本来就是随机的，所以再随机并没有意义
-    // let random_pivot = rand::thread_rng().gen_range(first..end);
     let last = end - 1;
-    // unsafe {
-    //     ptr::swap_nonoverlapping(&mut vec[random_pivot], &mut vec[last], 1);
-    // }
     let mut i = first;
     for j in first..last {
         // 最后一个是待换的
-        if compare(&vec[j], &vec[last]) {
-            unsafe {
-                ptr::swap_nonoverlapping(&mut vec[i], &mut vec[j], 1);
-            }
+        if compare(&slice[j], &slice[last]) {
+            slice.swap(i, j);
             i += 1;
         }
     }
-    unsafe {
-        ptr::swap_nonoverlapping(&mut vec[i], &mut vec[last], 1);
-    }
+    slice.swap(i, last);
     i
 }
 
+// 主元在pivot，返回(left_end, right_first)：[first, left_end)都不大于主元，[right_first, end)都不小于主元，
+// 两者之间至多一个元素，它等于主元，两边都比[first, end)短
+// 书中的x是主元的副本，这里元素不能复制，主元被交换走时要记下它的新位置
+// 两个指针停下时slice[i]不小于主元，slice[j]不大于主元，两个都等于主元时已经可以留在原来的一边，不用交换
+// 有序的区间里i和j之间的元素都等于主元，所以一次交换也不会发生
+fn hoare_partition<T>(
+    slice: &mut [T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    pivot: &mut usize,
+    first: usize,
+    end: usize
+) -> (usize, usize) {
+    let mut i = first;
+    let mut j = end - 1;
+    loop {
+        // 第一轮两个指针都会停在主元上，之后各自停在上一轮另一个指针留下的元素上，不会越界
+        while precedes(compare, &slice[i], &slice[*pivot]) {
+            i += 1;
+        }
+        while precedes(compare, &slice[*pivot], &slice[j]) {
+            j -= 1;
+        }
+        if i == j {
+            return (i, i + 1);
+        }
+        if i > j {
+            return (i, i);
+        }
+        if
+            precedes(compare, &slice[*pivot], &slice[i]) ||
+            precedes(compare, &slice[j], &slice[*pivot])
+        {
+            slice.swap(i, j);
+            if *pivot == i {
+                *pivot = j;
+            } else if *pivot == j {
+                *pivot = i;
+            }
+        }
+        i += 1;
+        j -= 1;
+    }
+}
+
+// 先按Hoare的方法分成两边，再把左边等于主元的元素挤到左边的末尾、右边等于主元的挤到右边的开头，
+// 返回等于主元的一段[lt, gt)
+// 两次挤压都只移动严格小于（大于）主元的元素，有序的区间依然一个元素也不会动
+fn three_way_partition<T>(
+    slice: &mut [T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    mut pivot: usize,
+    first: usize,
+    end: usize
+) -> (usize, usize) {
+    let (left_end, right_first) = hoare_partition(slice, compare, &mut pivot, first, end);
+
+    let mut lt = first;
+    for k in first..left_end {
+        if precedes(compare, &slice[k], &slice[pivot]) {
+            slice.swap(lt, k);
+            if pivot == lt {
+                pivot = k;
+            }
+            lt += 1;
+        }
+    }
+
+    let mut gt = end;
+    for k in (right_first..end).rev() {
+        if precedes(compare, &slice[pivot], &slice[k]) {
+            gt -= 1;
+            slice.swap(gt, k);
+            if pivot == gt {
+                pivot = k;
+            }
+        }
+    }
+    (lt, gt)
+}
+
+// 按classify的结果把[first, end)分成小于、等于、大于三段，返回等于的一段[lt, gt)
+// 荷兰国旗问题的解法，只扫一遍，不和某个元素比较，用于主元不是单个元素的情形
+fn dutch_flag_partition<T>(
+    slice: &mut [T],
+    first: usize,
+    end: usize,
+    classify: impl Fn(&T) -> Ordering
+) -> (usize, usize) {
+    let mut lt = first;
+    let mut i = first;
+    let mut gt = end;
+    while i < gt {
+        match classify(&slice[i]) {
+            Ordering::Less => {
+                slice.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                slice.swap(i, gt);
+            }
+            Ordering::Equal => {
+                i += 1;
+            }
+        }
+    }
+    (lt, gt)
+}
+
+// prev严格排在next之前
+// compare可能包含等于（<=）也可能不包含（<），两个方向都比一次才能区分“相等”和“在前”
+fn precedes<T>(compare: fn(prev: &T, next: &T) -> bool, prev: &T, next: &T) -> bool {
+    compare(prev, next) && !compare(next, prev)
+}
+
+struct PivotChooser {
+    pivot: Pivot,
+    random: XorShift64,
+}
+
+impl PivotChooser {
+    fn new(pivot: Pivot) -> PivotChooser {
+        let seed = if let Pivot::Random(seed) = pivot { seed } else { 0 };
+        PivotChooser { pivot, random: XorShift64::new(seed) }
+    }
+
+    // 返回主元在[first, end)中的下标
+    fn choose<T>(
+        &mut self,
+        slice: &[T],
+        compare: fn(prev: &T, next: &T) -> bool,
+        first: usize,
+        end: usize
+    ) -> usize {
+        let last = end - 1;
+        let mid = first + ((end - first) >> 1);
+        match self.pivot {
+            Pivot::Last => last,
+            Pivot::First => first,
+            Pivot::MedianOfThree => median_of_three(slice, compare, first, mid, last),
+            Pivot::Ninther => {
+                let len = end - first;
+                if len < NINTHER_THRESHOLD {
+                    median_of_three(slice, compare, first, mid, last)
+                } else {
+                    let step = len >> 3;
+                    let a = median_of_three(slice, compare, first, first + step, first + 2 * step);
+                    let b = median_of_three(slice, compare, mid - step, mid, mid + step);
+                    let c = median_of_three(slice, compare, last - 2 * step, last - step, last);
+                    median_of_three(slice, compare, a, b, c)
+                }
+            }
+            Pivot::Random(_) => first + self.random.below(end - first),
+        }
+    }
+}
+
+// 区间较短时九数取中的收益抵不上多出来的比较
+const NINTHER_THRESHOLD: usize = 40;
+
+fn median_of_three<T>(
+    slice: &[T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    a: usize,
+    b: usize,
+    c: usize
+) -> usize {
+    if compare(&slice[a], &slice[b]) {
+        if compare(&slice[b], &slice[c]) {
+            b
+        } else if compare(&slice[a], &slice[c]) {
+            c
+        } else {
+            a
+        }
+    } else if compare(&slice[a], &slice[c]) {
+        a
+    } else if compare(&slice[b], &slice[c]) {
+        c
+    } else {
+        b
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use algorithms_prelude::permutation;
+    use algorithms_prelude::stack::{ run_with_stack, SMALL_STACK };
     #[test]
     fn it_sort_ascending() {
         let mut v = vec![22, 43, 145, 1, 9];
@@ -240,4 +531,195 @@ mod test {
 
         assert_eq!(v, sorted_v);
     }
+
+    #[test]
+    fn it_sort_with_every_pivot() {
+        let pivots = [
+            Pivot::Last,
+            Pivot::First,
+            Pivot::MedianOfThree,
+            Pivot::Ninther,
+            Pivot::Random(7),
+        ];
+        let mut random = XorShift64::new(1);
+        let mut v: Vec<u32> = (0..1000).map(|_| (random.next_u64() % 100) as u32).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        for pivot in pivots {
+            let mut sorted = v.clone();
+            QuickSorterWith(&mut sorted, QuickSortConfig::new().pivot(pivot))
+                .sort_by(|prev, next| prev < next);
+            assert_eq!(sorted, expected, "{:?}", pivot);
+        }
+
+        // 相同的种子，相同的输入，结果（包括相等元素的相对顺序）也相同
+        let mut pairs: Vec<(u32, usize)> = v.drain(..).zip(0..).collect();
+        let mut again = pairs.clone();
+        QuickSorterWith(&mut pairs, QuickSortConfig::new().pivot(Pivot::Random(7)))
+            .sort_by(|prev, next| prev.0 < next.0);
+        QuickSorterWith(&mut again, QuickSortConfig::new().pivot(Pivot::Random(7)))
+            .sort_by(|prev, next| prev.0 < next.0);
+        assert_eq!(pairs, again);
+    }
+
+    #[test]
+    fn it_sort_sorted_million() {
+        // 已排序、逆序的输入都不会退化成O(n^2)
+        for pivot in [Pivot::MedianOfThree, Pivot::Ninther, Pivot::Random(2024)] {
+            let mut v: Vec<u32> = (0..1_000_000).collect();
+            QuickSorterWith(&mut v, QuickSortConfig::new().pivot(pivot))
+                .sort_by(|prev, next| prev < next);
+            assert!(v.windows(2).all(|w| w[0] < w[1]));
+            QuickSorterWith(&mut v, QuickSortConfig::new().pivot(pivot))
+                .sort_by(|prev, next| prev > next);
+            assert!(v.windows(2).all(|w| w[0] > w[1]));
+        }
+    }
+
+    #[test]
+    fn it_sort_with_every_partition() {
+        let mut random = XorShift64::new(3);
+        let v: Vec<(u32, usize)> = (0..2000).map(|i| ((random.next_u64() % 7) as u32, i)).collect();
+        let mut expected = v.clone();
+        expected.sort_by_key(|e| e.0);
+        for partition in [Partition::Lomuto, Partition::Hoare, Partition::ThreeWay] {
+            for compare in [
+                (|prev, next| prev.0 < next.0) as fn(&(u32, usize), &(u32, usize)) -> bool,
+                |prev, next| prev.0 <= next.0,
+            ] {
+                let mut sorted = v.clone();
+                QuickSorterWith(&mut sorted, QuickSortConfig::new().partition(partition))
+                    .sort_by(compare);
+                let keys: Vec<u32> = sorted.iter().map(|e| e.0).collect();
+                let expected_keys: Vec<u32> = expected.iter().map(|e| e.0).collect();
+                assert_eq!(keys, expected_keys, "{:?}", partition);
+            }
+        }
+
+        for len in 0..20 {
+            let mut v: Vec<usize> = (0..len).rev().collect();
+            QuickSorterWith(&mut v, QuickSortConfig::new().partition(Partition::Hoare))
+                .sort_by(|prev, next| prev < next);
+            assert_eq!(v, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn it_keep_sorted_input_untouched() {
+        // 有序的输入，compare包含等于时，Hoare和ThreeWay一个元素也不移动，相等元素的顺序不变
+        let mut random = XorShift64::new(29);
+        let mut keys: Vec<u32> = (0..10_000).map(|_| (random.next_u64() % 50) as u32).collect();
+        keys.sort();
+        let v: Vec<(u32, usize)> = keys.into_iter().zip(0..).collect();
+        let pivots = [
+            Pivot::Last,
+            Pivot::First,
+            Pivot::MedianOfThree,
+            Pivot::Ninther,
+            Pivot::Random(9),
+        ];
+        for partition in [Partition::Hoare, Partition::ThreeWay] {
+            for pivot in pivots {
+                let mut sorted = v.clone();
+                let config = QuickSortConfig::new().pivot(pivot).partition(partition);
+                QuickSorterWith(&mut sorted, config).sort_by(|prev, next| prev.0 <= next.0);
+                assert_eq!(sorted, v, "{:?} {:?}", pivot, partition);
+            }
+        }
+
+        let mut all_equal: Vec<(u8, usize)> = (0..100_000).map(|i| (7, i)).collect();
+        let expected = all_equal.clone();
+        QuickSorter(&mut all_equal).sort_by(|prev, next| prev.0 <= next.0);
+        assert_eq!(all_equal, expected);
+    }
+
+    #[test]
+    fn it_sort_few_distinct_keys() {
+        // 一百万个元素只有三种键，Lomuto会退化成O(n^2)，这两种划分都是近似线性的
+        let mut random = XorShift64::new(4);
+        let v: Vec<u8> = (0..1_000_000).map(|_| (random.next_u64() % 3) as u8).collect();
+        for partition in [Partition::Hoare, Partition::ThreeWay] {
+            let mut sorted = v.clone();
+            QuickSorterWith(&mut sorted, QuickSortConfig::new().partition(partition))
+                .sort_by(|prev, next| prev < next);
+            assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        }
+
+        let mut all_equal = vec![7u8; 1_000_000];
+        QuickSorterWith(&mut all_equal, QuickSortConfig::new().partition(Partition::ThreeWay))
+            .sort_by(|prev, next| prev < next);
+        assert!(all_equal.iter().all(|&e| e == 7));
+    }
+
+    #[test]
+    fn it_sort_with_small_stack() {
+        // 主元总取最后一个、输入有序，每次划分都是n - 1和0，递归版本会有n层
+        // 只对短的一边递归后，栈深度是常数，时间依然是O(n^2)，所以n不能太大
+        let mut v: Vec<u32> = (0..10_000).collect();
+        run_with_stack(SMALL_STACK, || {
+            QuickSorterWith(&mut v, QuickSortConfig::new().pivot(Pivot::Last))
+                .sort_by(|prev, next| prev < next)
+        });
+        assert!(v.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    // 少数几种键、逆序、有序、全部相等，分别用对它们不会退化的配置
+    fn sort_adversarial_with_small_stack(len: u32) {
+        let mut random = XorShift64::new(8);
+        let few_keys: Vec<u32> = (0..len).map(|_| (random.next_u64() % 4) as u32).collect();
+        run_with_stack(SMALL_STACK, || {
+            let mut sorted = few_keys.clone();
+            let config = QuickSortConfig::new().partition(Partition::ThreeWay);
+            QuickSorterWith(&mut sorted, config).sort_by(|prev, next| prev < next);
+            assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+
+            let mut sorted: Vec<u32> = (0..len).rev().collect();
+            QuickSorterWith(&mut sorted, QuickSortConfig::new().pivot(Pivot::Random(8)))
+                .sort_by(|prev, next| prev < next);
+            assert_eq!(sorted, (0..len).collect::<Vec<_>>());
+
+            QuickSorter(&mut sorted).sort_by(|prev, next| prev > next);
+            assert_eq!(sorted, (0..len).rev().collect::<Vec<_>>());
+
+            let mut all_equal = vec![7u32; len as usize];
+            QuickSorter(&mut all_equal).sort_by(|prev, next| prev < next);
+            assert!(all_equal.iter().all(|&e| e == 7));
+        });
+    }
+
+    #[test]
+    fn it_sort_adversarial_with_small_stack() {
+        sort_adversarial_with_small_stack(200_000);
+    }
+
+    #[test]
+    #[ignore = "10^7个元素，用cargo test --release -- --ignored运行"]
+    fn it_sort_ten_million_with_small_stack() {
+        sort_adversarial_with_small_stack(10_000_000);
+    }
+
+    #[test]
+    fn it_arg_sort() {
+        let mut random = XorShift64::new(36);
+        let v: Vec<u32> = (0..10_000).map(|_| random.next_u64() as u32 % 100).collect();
+        let compare = |prev: &u32, next: &u32| prev < next;
+        let mut perms = vec![
+            permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                IntroSorter(indexed).sort_by(compare)
+            })
+        ];
+        for partition in [Partition::Lomuto, Partition::Hoare, Partition::ThreeWay] {
+            let config = QuickSortConfig::new().pivot(Pivot::Random(0)).partition(partition);
+            perms.push(
+                permutation::arg_sort_with(&v, compare, |indexed, compare| {
+                    QuickSorterWith(indexed, config).sort_by(compare)
+                })
+            );
+        }
+        for perm in perms {
+            let mut sorted = v.clone();
+            permutation::apply_permutation(&mut sorted, &perm).unwrap();
+            assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
 }
@@ -6,6 +6,7 @@ use std::env;
 // 构建一个序列，使得存在某个“主元”，主元左边的比主元小，右边的比主元大
 // 最坏时间复杂度为O(n^2)，平均复杂度为O(nlgn)，key越各异，排序越随机，越接近平均情形。
 // 反之，越接近已排序，同Key的数据越多，越接近最坏情形。
+// 默认用三数取中选主元、Hoare的方法划分，已排序的输入不再是最坏情形。
 
 fn main() {
     let mut int_array: Vec<i32> = env
@@ -0,0 +1,121 @@
+use std::ops::RangeInclusive;
+
+use crate::{ checked_range, count_len, detect_range, CountingKey, CountingSortError };
+
+// 美国国旗排序（American flag sort），原地版本的计数排序
+// 和计数排序一样先统计每个键的个数，得到每个桶在数组中的起止位置，
+// 然后不借助第二个数组，而是沿着置换环把元素直接交换到它所属的桶里：
+// 拿起当前位置的元素，和它所属桶的下一个空位交换，换回来的元素继续这样处理，
+// 直到换回来的元素正好属于当前桶。每次交换至少让一个元素归位，所以交换不超过n次
+// 额外空间只有两个长度为k的数组，是O(k)的，代价是排序不再稳定
+// 适合大数组、小范围键的情况，比如按u8或者按少量类别排序
+
+// 键在lo..=hi之间的原地计数排序，不稳定
+pub fn american_flag_sort_in_range<T, K, F>(
+    slice: &mut [T],
+    range: RangeInclusive<K>,
+    key: F
+) -> Result<(), CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    let (lo, hi) = range.into_inner();
+    let count_len = checked_range(slice, lo, hi, &key)?;
+    permute(slice, count_len, |e| key(e).offset_from(lo) as usize);
+    Ok(())
+}
+
+// 先扫描一遍求出键的范围，再原地计数排序，不稳定
+pub fn american_flag_sort_by_key<T, K, F>(slice: &mut [T], key: F) -> Result<(), CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    if let Some((lo, hi)) = detect_range(slice, &key) {
+        let count_len = count_len(lo, hi, slice.len())?;
+        permute(slice, count_len, |e| key(e).offset_from(lo) as usize);
+    }
+    Ok(())
+}
+
+// index把元素映射到0..count_len，调用者保证不越界
+fn permute<T, F>(slice: &mut [T], count_len: usize, index: F)
+    where F: Fn(&T) -> usize
+{
+    let mut next = vec![0; count_len]; // 每个桶的下一个空位
+    for e_ref in slice.iter() {
+        next[index(e_ref)] += 1;
+    }
+    let mut start = 0;
+    for n in next.iter_mut() {
+        let count = *n;
+        *n = start;
+        start += count;
+    }
+    // 桶b的结束位置就是桶b + 1的起始位置
+    let mut end: Vec<usize> = next[1..].to_vec();
+    end.push(slice.len());
+
+    for b in 0..count_len {
+        while next[b] < end[b] {
+            let d = index(&slice[next[b]]);
+            if d != b {
+                slice.swap(next[b], next[d]);
+            }
+            next[d] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::counting_sort_by_key;
+    use algorithms_prelude::random::XorShift64;
+
+    #[test]
+    fn it_sort_small_range_in_place() -> Result<(), CountingSortError> {
+        let mut random = XorShift64::new(35);
+        let mut v: Vec<u8> = (0..1_000_000).map(|_| random.next_u64() as u8).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        let ptr = v.as_ptr();
+        american_flag_sort_by_key(&mut v, |&e| e)?;
+        assert_eq!(v.as_ptr(), ptr);
+        assert_eq!(v, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn it_sort_negative_keys_in_range() -> Result<(), CountingSortError> {
+        let mut v = vec![(3, "a"), (-5, "b"), (0, "c"), (-1, "d"), (7, "e"), (-5, "f"), (0, "g")];
+        american_flag_sort_in_range(&mut v, -5..=7, |e: &(i16, _)| e.0)?;
+        // 不稳定，只比较键
+        let keys: Vec<i16> = v.iter().map(|e| e.0).collect();
+        assert_eq!(keys, vec![-5, -5, -1, 0, 0, 3, 7]);
+
+        assert_eq!(
+            american_flag_sort_in_range(&mut v, 0..=7, |e| e.0),
+            Err(CountingSortError::KeyOutOfRange)
+        );
+        assert_eq!(
+            american_flag_sort_by_key(&mut [0u64, 1 << 40], |&e| e),
+            Err(CountingSortError::RangeTooLarge { range: (1 << 40) + 1, len: 2 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_agree_with_counting_sort() -> Result<(), CountingSortError> {
+        // 同一个键的元素顺序可能不同，但键的序列和每个桶的元素集合都一样
+        let mut random = XorShift64::new(0);
+        let mut v: Vec<(i32, usize)> = (0..5000)
+            .map(|i| ((random.below(100) as i32) - 50, i))
+            .collect();
+        let expected = counting_sort_by_key(v.clone(), |e| e.0)?;
+        american_flag_sort_by_key(&mut v, |e| e.0)?;
+        for (a, b) in v.chunk_by_mut(|x, y| x.0 == y.0).zip(expected.chunk_by(|x, y| x.0 == y.0)) {
+            a.sort();
+            assert_eq!(a, b);
+        }
+        assert_eq!(v.len(), expected.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{ count_len, detect_range, distribute, CountingKey, CountingSortError };
+
+// 预处理后O(1)回答区间计数（练习8.2-4）
+// 计数排序第三步算出的前缀和就是累积分布：小于等于键k的元素个数，
+// 那么落在[a, b]中的元素个数是C[b] - C[a - 1]，一次减法
+// 这里保存的是“小于”的版本less，less[i]是键小于lo + i的元素个数，多存一项元素总数，
+// 这样区间计数是less[b - lo + 1] - less[a - lo]，不用特判a == lo
+pub struct KeyHistogram<K> {
+    lo: Option<K>, // 没有元素时为None
+    less: Vec<usize>,
+}
+
+impl<K: CountingKey> KeyHistogram<K> {
+    // 只统计不排序，范围过大时和计数排序一样返回错误
+    pub fn from_keys<T, F>(slice: &[T], key: F) -> Result<KeyHistogram<K>, CountingSortError>
+        where F: Fn(&T) -> K
+    {
+        let (lo, hi) = match detect_range(slice, &key) {
+            Some(range) => range,
+            None => {
+                return Ok(KeyHistogram { lo: None, less: vec![0] });
+            }
+        };
+        let mut less = vec![0; count_len(lo, hi, slice.len())? + 1];
+        for e_ref in slice {
+            less[(key(e_ref).offset_from(lo) as usize) + 1] += 1;
+        }
+        for i in 1..less.len() {
+            less[i] += less[i - 1];
+        }
+        Ok(KeyHistogram { lo: Some(lo), less })
+    }
+
+    pub fn len(&self) -> usize {
+        self.less[self.less.len() - 1]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 键的最小值和最大值
+    pub fn range(&self) -> Option<(K, K)> {
+        self.lo.map(|lo| (lo, K::add_offset(lo, (self.less.len() - 2) as u128)))
+    }
+
+    // 键为key的元素个数
+    pub fn count(&self, key: K) -> usize {
+        self.count_in(key, key)
+    }
+
+    // 键在[a, b]中的元素个数，O(1)
+    pub fn count_in(&self, a: K, b: K) -> usize {
+        let (lo, hi) = match self.range() {
+            Some(range) => range,
+            None => {
+                return 0;
+            }
+        };
+        let a = a.max(lo);
+        let b = b.min(hi);
+        if a > b {
+            return 0;
+        }
+        let a = a.offset_from(lo) as usize;
+        let b = b.offset_from(lo) as usize;
+        self.less[b + 1] - self.less[a]
+    }
+
+    // 键不超过key的元素个数，即累积分布
+    pub fn count_at_most(&self, key: K) -> usize {
+        match self.range() {
+            Some((lo, hi)) if key >= lo => self.less[(key.min(hi).offset_from(lo) as usize) + 1],
+            _ => 0,
+        }
+    }
+
+    // 直方图，从小到大依次是每个键和它的个数，包括个数为0的键
+    pub fn histogram(&self) -> impl Iterator<Item = (K, usize)> + '_ {
+        self.keys().zip(self.less.windows(2).map(|w| w[1] - w[0]))
+    }
+
+    // 累积分布，从小到大依次是每个键和不超过它的元素个数
+    pub fn cumulative(&self) -> impl Iterator<Item = (K, usize)> + '_ {
+        self.keys().zip(self.less[1..].iter().copied())
+    }
+
+    // 第p百分位数（0 <= p <= 100），用最近秩法：排序后第⌈p / 100 * n⌉个元素的键，p = 0时取最小值
+    // 在累积分布上二分查找，O(lgk)
+    pub fn percentile(&self, p: f64) -> Option<K> {
+        let lo = self.lo?;
+        if !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let rank = ((p / 100.0) * (self.len() as f64)).ceil().max(1.0) as usize;
+        // 第一个使less[i + 1] >= rank的i
+        let i = self.less[1..].partition_point(|&c| c < rank);
+        Some(K::add_offset(lo, i as u128))
+    }
+
+    fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        let lo = self.lo;
+        (0..self.less.len() - 1).map(move |i| K::add_offset(lo.unwrap(), i as u128))
+    }
+}
+
+// 计数排序，同时返回排序时得到的直方图，不用为统计再扫描一遍
+pub fn counting_sort_with_histogram<T, K, F>(
+    vec: Vec<T>,
+    key: F
+) -> Result<(Vec<T>, KeyHistogram<K>), CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    let (lo, hi) = match detect_range(&vec, &key) {
+        Some(range) => range,
+        None => {
+            return Ok((vec, KeyHistogram { lo: None, less: vec![0] }));
+        }
+    };
+    let count_len = count_len(lo, hi, vec.len())?;
+    let len = vec.len();
+    let (result, mut less) = distribute(vec, count_len, |e| key(e).offset_from(lo) as usize);
+    less.push(len);
+    Ok((result, KeyHistogram { lo: Some(lo), less }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_count_in_range() -> Result<(), CountingSortError> {
+        let v: Vec<i32> = vec![3, -2, 0, 3, 7, -2, 3, 5];
+        let histogram = KeyHistogram::from_keys(&v, |&e| e)?;
+        assert_eq!(histogram.len(), 8);
+        assert_eq!(histogram.range(), Some((-2, 7)));
+        assert_eq!(histogram.count(3), 3);
+        assert_eq!(histogram.count(4), 0);
+        assert_eq!(histogram.count_in(-2, 3), 6);
+        assert_eq!(histogram.count_in(1, 6), 4);
+        // 超出范围的部分被截掉，空区间为0
+        assert_eq!(histogram.count_in(-100, 100), 8);
+        assert_eq!(histogram.count_in(8, 100), 0);
+        assert_eq!(histogram.count_in(5, 4), 0);
+        assert_eq!(histogram.count_at_most(-3), 0);
+        assert_eq!(histogram.count_at_most(4), 6);
+        assert_eq!(histogram.count_at_most(i32::MAX), 8);
+        Ok(())
+    }
+
+    #[test]
+    fn it_histogram_from_sorting() -> Result<(), CountingSortError> {
+        let v: Vec<u8> = vec![2, 0, 2, 4, 0, 2];
+        let (sorted, histogram) = counting_sort_with_histogram(v.clone(), |&e| e)?;
+        assert_eq!(sorted, vec![0, 0, 2, 2, 2, 4]);
+        assert_eq!(
+            histogram.histogram().collect::<Vec<_>>(),
+            vec![(0, 2), (1, 0), (2, 3), (3, 0), (4, 1)]
+        );
+        assert_eq!(
+            histogram.cumulative().collect::<Vec<_>>(),
+            vec![(0, 2), (1, 2), (2, 5), (3, 5), (4, 6)]
+        );
+        // 排序时得到的和单独统计的一样
+        let counted = KeyHistogram::from_keys(&v, |&e| e)?;
+        assert!(histogram.cumulative().eq(counted.cumulative()));
+
+        let (empty, histogram) = counting_sort_with_histogram(Vec::<u8>::new(), |&e| e)?;
+        assert!(empty.is_empty() && histogram.is_empty());
+        assert_eq!(histogram.histogram().count(), 0);
+        assert_eq!(histogram.count_in(0, 255), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_percentile() -> Result<(), CountingSortError> {
+        // 1..=100各一个，第p百分位数就是p
+        let v: Vec<i64> = (1..=100).rev().collect();
+        let histogram = KeyHistogram::from_keys(&v, |&e| e)?;
+        assert_eq!(histogram.percentile(0.0), Some(1));
+        assert_eq!(histogram.percentile(50.0), Some(50));
+        assert_eq!(histogram.percentile(50.5), Some(51));
+        assert_eq!(histogram.percentile(99.0), Some(99));
+        assert_eq!(histogram.percentile(100.0), Some(100));
+        assert_eq!(histogram.percentile(100.1), None);
+        assert_eq!(histogram.percentile(f64::NAN), None);
+
+        // 重复的键
+        let v = [5u16, 1, 5, 5, 9];
+        let histogram = KeyHistogram::from_keys(&v, |&e| e)?;
+        assert_eq!(histogram.percentile(20.0), Some(1));
+        assert_eq!(histogram.percentile(21.0), Some(5));
+        assert_eq!(histogram.percentile(80.0), Some(5));
+        assert_eq!(histogram.percentile(81.0), Some(9));
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
-use std::ptr;
+use std::{ error::Error, fmt, ops::RangeInclusive };
+
+mod american_flag;
+mod histogram;
+
+pub use american_flag::{ american_flag_sort_by_key, american_flag_sort_in_range };
+pub use histogram::{ counting_sort_with_histogram, KeyHistogram };
 
 pub fn counting_sort<T, F>(
     vec: Vec<T>,
@@ -7,33 +13,209 @@ pub fn counting_sort<T, F>(
 ) -> Result<Vec<T>, &'static str>
     where F: Fn(&T) -> usize
 {
-    let mut count = vec![0; max_key];
     for e_ref in &vec {
-        let key = enumerate(e_ref);
-        if key < max_key {
-            count[key] += 1; // 统计同key量
-        } else {
+        if enumerate(e_ref) >= max_key {
             return Err("元素值溢出");
         }
     }
+    Ok(distribute(vec, max_key, enumerate).0)
+}
 
-    for i in 1..count.len() {
-        count[i] += count[i - 1];
+// 计数数组长度的上限是元素个数的这么多倍，超过了就拒绝排序
+// 计数排序是O(n + k)的，k远大于n时，时间和空间都花在计数数组上，不如用比较排序
+pub const MAX_RANGE_FACTOR: usize = 16;
+// 元素很少时也允许不超过这个长度的计数数组，比如对几个u8排序
+pub const MIN_COUNT_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountingSortError {
+    // 给定的范围lo..=hi中lo > hi
+    EmptyRange,
+    // 有元素的键不在给定范围内
+    KeyOutOfRange,
+    // 计数数组的长度range相对元素个数len过大
+    RangeTooLarge {
+        range: u128,
+        len: usize,
+    },
+}
+
+impl fmt::Display for CountingSortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountingSortError::EmptyRange => write!(f, "键的范围为空"),
+            CountingSortError::KeyOutOfRange => write!(f, "元素值溢出"),
+            CountingSortError::RangeTooLarge { range, len } =>
+                write!(f, "键的范围{}相对元素个数{}过大", range, len),
+        }
     }
+}
 
-    let result_len = vec.len();
-    let mut result = Vec::with_capacity(result_len); // 应该定义好容量，而不是new，new出来的vec容量为0
-    unsafe {
-        result.set_len(result_len);
+impl Error for CountingSortError {}
+
+// 可以作为计数排序的键的整数类型
+pub trait CountingKey: Copy + Ord {
+    // self - lo，调用者保证lo <= self
+    fn offset_from(self, lo: Self) -> u128;
+
+    // lo + offset，offset_from的逆运算，调用者保证不溢出
+    fn add_offset(lo: Self, offset: u128) -> Self;
+}
+
+macro_rules! impl_counting_key {
+    ($($t:ty),*) => {
+        $(
+            impl CountingKey for $t {
+                // 有符号数转u128是符号扩展，按2^128取模相减，结果就是真实的差
+                fn offset_from(self, lo: Self) -> u128 {
+                    (self as u128).wrapping_sub(lo as u128)
+                }
+
+                fn add_offset(lo: Self, offset: u128) -> Self {
+                    (lo as u128).wrapping_add(offset) as Self
+                }
+            }
+        )*
+    };
+}
+
+impl_counting_key!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// 键在lo..=hi之间的计数排序，键可以是任意整数类型，包括负数
+pub fn counting_sort_in_range<T, K, F>(
+    vec: Vec<T>,
+    range: RangeInclusive<K>,
+    key: F
+) -> Result<Vec<T>, CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    let (lo, hi) = range.into_inner();
+    let count_len = checked_range(&vec, lo, hi, &key)?;
+    Ok(distribute(vec, count_len, |e| key(e).offset_from(lo) as usize).0)
+}
+
+// 先扫描一遍求出键的最小值和最大值，再按这个范围计数排序
+pub fn counting_sort_by_key<T, K, F>(vec: Vec<T>, key: F) -> Result<Vec<T>, CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    let (lo, hi) = match detect_range(&vec, &key) {
+        Some(range) => range,
+        None => {
+            return Ok(vec);
+        }
+    };
+    let count_len = count_len(lo, hi, vec.len())?;
+    Ok(distribute(vec, count_len, |e| key(e).offset_from(lo) as usize).0)
+}
+
+// 以下是间接排序的版本，不移动元素，返回排序后每个位置上元素的原下标
+// 实现上就是对下标0..n做计数排序，键取自对应的元素，所以同样是稳定的
+
+pub fn arg_counting_sort<T, F>(
+    slice: &[T],
+    max_key: usize,
+    enumerate: F
+) -> Result<Vec<usize>, &'static str>
+    where F: Fn(&T) -> usize
+{
+    counting_sort((0..slice.len()).collect(), max_key, |&i| enumerate(&slice[i]))
+}
+
+pub fn arg_counting_sort_in_range<T, K, F>(
+    slice: &[T],
+    range: RangeInclusive<K>,
+    key: F
+) -> Result<Vec<usize>, CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    counting_sort_in_range((0..slice.len()).collect(), range, |&i| key(&slice[i]))
+}
+
+pub fn arg_counting_sort_by_key<T, K, F>(
+    slice: &[T],
+    key: F
+) -> Result<Vec<usize>, CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    counting_sort_by_key((0..slice.len()).collect(), |&i| key(&slice[i]))
+}
+
+// 键的最小值和最大值，没有元素时返回None
+fn detect_range<T, K, F>(slice: &[T], key: &F) -> Option<(K, K)>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    let mut iter = slice.iter();
+    let first = key(iter.next()?);
+    let (mut lo, mut hi) = (first, first);
+    for e_ref in iter {
+        let k = key(e_ref);
+        if k < lo {
+            lo = k;
+        } else if k > hi {
+            hi = k;
+        }
     }
-    for e in vec.into_iter().rev() {
-        let key = enumerate(&e);
-        unsafe {
-            ptr::write(&mut result[count[key] - 1], e); // 注意下标
+    Some((lo, hi))
+}
+
+// 检查给定的范围和每个元素的键，返回计数数组长度
+fn checked_range<T, K, F>(slice: &[T], lo: K, hi: K, key: &F) -> Result<usize, CountingSortError>
+    where K: CountingKey, F: Fn(&T) -> K
+{
+    if lo > hi {
+        return Err(CountingSortError::EmptyRange);
+    }
+    let count_len = count_len(lo, hi, slice.len())?;
+    for e_ref in slice {
+        let k = key(e_ref);
+        if k < lo || k > hi {
+            return Err(CountingSortError::KeyOutOfRange);
         }
-        count[key] -= 1;
     }
-    Ok(result)
+    Ok(count_len)
+}
+
+// 范围lo..=hi需要的计数数组长度，过大时返回错误
+fn count_len<K: CountingKey>(lo: K, hi: K, len: usize) -> Result<usize, CountingSortError> {
+    // hi - lo最大是2^128 - 1，加一会溢出，溢出时肯定也过大
+    let range = hi.offset_from(lo).checked_add(1);
+    let limit = len.saturating_mul(MAX_RANGE_FACTOR).max(MIN_COUNT_LEN);
+    match range {
+        Some(range) if range <= (limit as u128) => Ok(range as usize),
+        _ =>
+            Err(CountingSortError::RangeTooLarge {
+                range: range.unwrap_or(u128::MAX),
+                len,
+            }),
+    }
+}
+
+// 计数排序的主体，index把元素映射到0..count_len，调用者保证不越界
+// 同时返回每个键在结果中的起始位置，也就是小于这个键的元素个数
+// 每个元素的键只取一次，存下来给计数和放置共用：index是Fn，却可能借助Cell之类每次返回不同的值，
+// 两遍分别取键时两边对不上，放置时就会有的位置没放、有的位置放了两次
+fn distribute<T, F>(vec: Vec<T>, count_len: usize, index: F) -> (Vec<T>, Vec<usize>)
+    where F: Fn(&T) -> usize
+{
+    let keys: Vec<usize> = vec.iter().map(index).collect();
+    let mut count = vec![0; count_len];
+    for &key in &keys {
+        count[key] += 1; // 统计同key量
+    }
+
+    for i in 1..count.len() {
+        count[i] += count[i - 1];
+    }
+
+    // 元素不一定能复制，先放进Option的槽里，每个槽恰好放一个
+    let mut slots: Vec<Option<T>> = vec.iter().map(|_| None).collect();
+    for (e, key) in vec.into_iter().zip(keys).rev() {
+        count[key] -= 1; // 注意下标
+        slots[count[key]] = Some(e);
+    }
+    let result = slots.into_iter().flatten().collect();
+    // 放完以后count[key]减到了键key的第一个位置
+    (result, count)
 }
 
 #[cfg(test)]
@@ -41,8 +223,9 @@ mod test {
     const MAX_VALUE: usize = 10;
 
     use super::*;
+    use std::cell::Cell;
     use issort::InsertionSorter;
-    use algorithms_prelude::CompareSorter;
+    use algorithms_prelude::{ permutation::apply_permutation, CompareSorter };
     #[test]
     fn it_sort_ascending() {
         let v = vec![2, 4, 1, 7, 9, 9, 5, 5, 2, 4, 2, 3];
@@ -233,4 +416,94 @@ mod test {
         assert_eq!(result2, expected);
         Ok(())
     }
+
+    #[test]
+    fn it_sort_negative_keys_in_range() -> Result<(), CountingSortError> {
+        let v: Vec<i32> = vec![3, -5, 0, -1, 7, -5, 2, -3];
+        let result = counting_sort_in_range(v, -5..=7, |&e| e)?;
+        assert_eq!(result, vec![-5, -5, -3, -1, 0, 2, 3, 7]);
+
+        // 键不在范围内，或者范围本身为空
+        assert_eq!(
+            counting_sort_in_range(vec![1i8, -1], 0..=10, |&e| e),
+            Err(CountingSortError::KeyOutOfRange)
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 10..=0;
+        assert_eq!(
+            counting_sort_in_range(vec![1i8], empty, |&e| e),
+            Err(CountingSortError::EmptyRange)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_sort_by_detected_range() -> Result<(), CountingSortError> {
+        // 键的范围是[i64::MAX - 3, i64::MAX]和[i8::MIN, i8::MIN + 2]，都贴着类型的边界
+        let v = vec![i64::MAX, i64::MAX - 3, i64::MAX - 1, i64::MAX - 3];
+        let result = counting_sort_by_key(v, |&e| e)?;
+        assert_eq!(result, vec![i64::MAX - 3, i64::MAX - 3, i64::MAX - 1, i64::MAX]);
+
+        let v = vec![(i8::MIN + 2, "a"), (i8::MIN, "b"), (i8::MIN + 2, "c"), (i8::MIN, "d")];
+        let result = counting_sort_by_key(v, |e| e.0)?;
+        assert_eq!(
+            result,
+            vec![(i8::MIN, "b"), (i8::MIN, "d"), (i8::MIN + 2, "a"), (i8::MIN + 2, "c")]
+        );
+
+        let empty: Vec<u128> = vec![];
+        assert_eq!(counting_sort_by_key(empty, |&e| e)?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_refuse_large_range() {
+        // 2个元素却要2^32的计数数组
+        let result = counting_sort_by_key(vec![0u32, u32::MAX], |&e| e);
+        assert_eq!(result, Err(CountingSortError::RangeTooLarge { range: 1 << 32, len: 2 }));
+
+        // 范围是整个i128，长度加一也放不下
+        let result = counting_sort_in_range(vec![0i128], i128::MIN..=i128::MAX, |&e| e);
+        assert_eq!(result, Err(CountingSortError::RangeTooLarge { range: u128::MAX, len: 1 }));
+
+        // 元素足够多时同样的范围是可以接受的
+        let v: Vec<u16> = (0..=u16::MAX).rev().step_by(8).collect();
+        let result = counting_sort_by_key(v, |&e| e).unwrap();
+        assert!(result.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn it_arg_sort_columns() -> Result<(), CountingSortError> {
+        // 按列存储的表，按年龄排序后用同一个排列重排每一列
+        let mut ages: Vec<i16> = vec![30, -1, 25, 30, 25];
+        let mut names = vec!["ZS", "LS", "WW", "ZL", "SQ"];
+        let perm = arg_counting_sort_by_key(&ages, |&e| e)?;
+        assert_eq!(perm, vec![1, 2, 4, 0, 3]);
+        assert_eq!(arg_counting_sort_in_range(&ages, -1..=30, |&e| e)?, perm);
+        assert_eq!(arg_counting_sort(&ages, 32, |&e| (e + 1) as usize), Ok(perm.clone()));
+        apply_permutation(&mut ages, &perm).unwrap();
+        apply_permutation(&mut names, &perm).unwrap();
+        assert_eq!(ages, vec![-1, 25, 25, 30, 30]);
+        assert_eq!(names, vec!["LS", "WW", "SQ", "ZS", "ZL"]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_survive_inconsistent_keys() {
+        // 每次调用返回不同的键，排出来的顺序没有意义，但每个元素都恰好出现一次
+        let calls = Cell::new(0);
+        let key = |_: &String| {
+            calls.set(calls.get() + 1);
+            calls.get() % 4
+        };
+        let v: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut result = counting_sort(v.clone(), 4, key).unwrap();
+        let mut again = counting_sort_in_range(v.clone(), 0..=3, key).unwrap();
+        result.sort();
+        again.sort();
+        let mut expected = v;
+        expected.sort();
+        assert_eq!(result, expected);
+        assert_eq!(again, expected);
+    }
 }
@@ -0,0 +1,41 @@
+// 比较LSD基数排序和快速排序在大量随机u64上的耗时
+// 用cargo bench -p radix_sort运行，不依赖第三方的基准测试框架，每项取几次中最快的一次
+use std::time::{ Duration, Instant };
+
+use algorithms_prelude::{ random::XorShift64, CompareSorter };
+use quick_sort::QuickSorter;
+use radix_sort::{ lsd_radix_sort, DigitBits };
+
+const LEN: usize = 10_000_000;
+const ROUNDS: usize = 3;
+
+fn measure(input: &[u64], sort: impl Fn(&mut Vec<u64>)) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let mut v = input.to_vec();
+            let start = Instant::now();
+            sort(&mut v);
+            let elapsed = start.elapsed();
+            assert!(v.windows(2).all(|w| w[0] <= w[1]));
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let mut random = XorShift64::new(7);
+    let input: Vec<u64> = (0..LEN).map(|_| random.next_u64()).collect();
+
+    let quick = measure(&input, |v| QuickSorter(v).sort_by(|prev, next| prev < next));
+    println!("{:<24}{:>14}{:>10}", "sorter", "time", "speedup");
+    println!("{:<24}{:>14?}{:>10}", "QuickSorter", quick, "1.00");
+    for (name, digit_bits) in [
+        ("LSD, 8-bit digits", DigitBits::Eight),
+        ("LSD, 11-bit digits", DigitBits::Eleven),
+    ] {
+        let elapsed = measure(&input, |v| lsd_radix_sort(v, digit_bits));
+        let speedup = quick.as_secs_f64() / elapsed.as_secs_f64();
+        println!("{:<24}{:>14?}{:>10.2}", name, elapsed, speedup);
+    }
+}
@@ -1,6 +1,45 @@
 use counting_sort::counting_sort;
 
+mod lsd;
+mod memcomparable;
+mod msd;
+mod radix_key;
+
+pub use lsd::{
+    arg_lsd_radix_sort,
+    arg_lsd_radix_sort_by_key,
+    lsd_radix_sort,
+    lsd_radix_sort_by_key,
+    DigitBits,
+};
+pub use memcomparable::{ Direction, KeyDecoder, KeyEncoder, KeyReader, Memcomparable };
+pub use msd::{
+    arg_msd_radix_sort,
+    arg_msd_radix_sort_by_key,
+    arg_multikey_quick_sort,
+    msd_radix_sort,
+    msd_radix_sort_by_key,
+    multikey_quick_sort,
+};
+pub use radix_key::RadixKey;
+
 pub fn radix_sort(arr: Vec<usize>, scale: usize, max_bit: u32) -> Result<Vec<usize>, &'static str> {
+    check_digits(&arr, scale, max_bit)?;
+    sort_by_digits(arr, scale, max_bit, |&e| e)
+}
+
+// 间接排序，返回排序后每个位置上元素的原下标
+// 每一轮对下标做计数排序，数位取自下标对应的元素
+pub fn arg_radix_sort(
+    arr: &[usize],
+    scale: usize,
+    max_bit: u32
+) -> Result<Vec<usize>, &'static str> {
+    check_digits(arr, scale, max_bit)?;
+    sort_by_digits((0..arr.len()).collect(), scale, max_bit, |&i| arr[i])
+}
+
+fn check_digits(arr: &[usize], scale: usize, max_bit: u32) -> Result<(), &'static str> {
     if let Some(max_value) = scale.checked_pow(max_bit) {
         for &e in arr.iter() {
             if e >= max_value {
@@ -10,11 +49,86 @@ pub fn radix_sort(arr: Vec<usize>, scale: usize, max_bit: u32) -> Result<Vec<usi
     } else {
         return Err("Max value overflow!");
     }
+    Ok(())
+}
+
+// 对任意元素按键做基数排序，键的字节数就是轮数，不用再给出进制和位数
+// 从最低的字节到最高的字节，每轮按一个字节做稳定的计数排序
+// 所有元素在某个字节上都相同时这一轮可以跳过，比如时间戳的高位字节，
+// 所以先扫描一遍找出有差别的字节
+// 每轮先把各个元素这一轮的字节取到digits里，每个元素只求一次key，统计和分配都用digits
+pub fn radix_sort_by_key<T, K, F>(vec: Vec<T>, key: F) -> Vec<T>
+    where K: RadixKey, F: Fn(&T) -> K
+{
+    let first = match vec.first() {
+        Some(e_ref) => key(e_ref),
+        None => {
+            return vec;
+        }
+    };
+    let mut varying = vec![false; K::BYTES];
+    for e_ref in &vec[1..] {
+        let k = key(e_ref);
+        for (i, v) in varying.iter_mut().enumerate() {
+            *v = *v || k.byte(i) != first.byte(i);
+        }
+    }
+
+    let mut result = vec;
+    let mut digits = vec![0u8; result.len()];
+    for i in (0..K::BYTES).rev() {
+        if !varying[i] {
+            continue;
+        }
+        for (digit, e_ref) in digits.iter_mut().zip(&result) {
+            *digit = key(e_ref).byte(i);
+        }
+
+        // 前缀和变成每个桶的起始位置
+        let mut count = [0; 256];
+        for &digit in &digits {
+            count[digit as usize] += 1;
+        }
+        let mut start = 0;
+        for c in count.iter_mut() {
+            let n = *c;
+            *c = start;
+            start += n;
+        }
+
+        // 元素不一定能复制，先放进Option的槽里，每个槽恰好放一个
+        let mut slots: Vec<Option<T>> = result.iter().map(|_| None).collect();
+        for (e, &digit) in result.into_iter().zip(&digits) {
+            slots[count[digit as usize]] = Some(e);
+            count[digit as usize] += 1;
+        }
+        result = slots.into_iter().flatten().collect();
+    }
+    result
+}
+
+// 间接排序，返回排序后每个位置上元素的原下标
+pub fn arg_radix_sort_by_key<T, K, F>(slice: &[T], key: F) -> Vec<usize>
+    where K: RadixKey, F: Fn(&T) -> K
+{
+    radix_sort_by_key((0..slice.len()).collect(), |&i| key(&slice[i]))
+}
 
+// 从最低位到最高位，每一位做一次稳定的计数排序
+fn sort_by_digits<T, F>(
+    arr: Vec<T>,
+    scale: usize,
+    max_bit: u32,
+    value: F
+) -> Result<Vec<T>, &'static str>
+    where F: Fn(&T) -> usize
+{
     let mut result = arr;
 
     for bit in 1..=max_bit {
-        result = counting_sort(result, scale, |&e| { (e % scale.pow(bit)) / scale.pow(bit - 1) })?;
+        result = counting_sort(result, scale, |e| {
+            (value(e) % scale.pow(bit)) / scale.pow(bit - 1)
+        })?;
     }
     Ok(result)
 }
@@ -22,6 +136,7 @@ pub fn radix_sort(arr: Vec<usize>, scale: usize, max_bit: u32) -> Result<Vec<usi
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::Cell;
 
     #[test]
     fn it_sort_ascending() -> Result<(), &'static str> {
@@ -30,4 +145,91 @@ mod tests {
         assert_eq!(v, vec![1, 9, 22, 43, 145]);
         Ok(())
     }
+
+    #[test]
+    fn it_arg_sort_ascending() -> Result<(), &'static str> {
+        let v = vec![22, 43, 145, 1, 9, 43];
+        let perm = arg_radix_sort(&v, 10, 3)?;
+        // 稳定，两个43保持原来的先后
+        assert_eq!(perm, vec![3, 4, 0, 1, 5, 2]);
+        assert_eq!(arg_radix_sort(&v, 10, 2), Err("Element overflow!"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_arg_sort_by_key() {
+        let v = vec![('b', -1i32), ('a', 7), ('b', -5), ('a', 7), ('a', -7)];
+        let perm = arg_radix_sort_by_key(&v, |&e| e);
+        assert_eq!(perm, vec![4, 1, 3, 2, 0]);
+        assert_eq!(arg_radix_sort_by_key(&v, |e| e.0), vec![1, 3, 4, 0, 2]);
+    }
+
+    #[test]
+    fn it_sort_by_timestamp() {
+        #[derive(Debug, PartialEq, Clone)]
+        struct Event {
+            timestamp: i64,
+            name: &'static str,
+        }
+
+        let v = vec![
+            Event {
+                timestamp: 1_700_000_000_123,
+                name: "c",
+            },
+            Event {
+                timestamp: -86_400_000,
+                name: "a",
+            },
+            Event {
+                timestamp: 1_700_000_000_000,
+                name: "b",
+            },
+            Event {
+                timestamp: 1_700_000_000_123,
+                name: "d",
+            },
+            Event {
+                timestamp: i64::MIN,
+                name: "min",
+            }
+        ];
+        let result = radix_sort_by_key(v, |e| e.timestamp);
+        let names: Vec<&str> = result.iter().map(|e| e.name).collect();
+        // 稳定，相同时间戳保持原来的先后
+        assert_eq!(names, vec!["min", "a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn it_sort_floats_and_composite_keys() {
+        let v = vec![3.5, -0.0, f64::NEG_INFINITY, 1e-300, -2.25, 0.0, f64::INFINITY];
+        let mut expected = v.clone();
+        expected.sort_by(f64::total_cmp);
+        assert_eq!(radix_sort_by_key(v, |&e| e), expected);
+
+        // 先按字符再按有符号数
+        let v = vec![('b', -1i32), ('a', 7), ('b', -5), ('中', 0), ('a', -7)];
+        let result = radix_sort_by_key(v, |&e| e);
+        assert_eq!(result, vec![('a', -7), ('a', 7), ('b', -5), ('b', -1), ('中', 0)]);
+
+        let v = vec![*b"bca", *b"abc", *b"bcb", *b"aab"];
+        assert_eq!(radix_sort_by_key(v, |e| *e), vec![*b"aab", *b"abc", *b"bca", *b"bcb"]);
+        assert_eq!(radix_sort_by_key(Vec::<u8>::new(), |&e| e), vec![]);
+    }
+
+    #[test]
+    fn it_extract_key_once_per_pass() {
+        // 键小于2^16，u32的四个字节里只有低两个字节有差别，排两轮
+        // 找有差别的字节扫描一遍，加上两轮，每个元素恰好求三次key
+        let calls = Cell::new(0);
+        let v: Vec<(u32, usize)> = (0..1000).map(|i| ((i * 7919) % 65536, i as usize)).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        let result = radix_sort_by_key(v, |e| {
+            calls.set(calls.get() + 1);
+            e.0
+        });
+        assert_eq!(result, expected);
+        assert_eq!(calls.get(), 3 * 1000);
+    }
 }
@@ -0,0 +1,208 @@
+use std::mem;
+
+use crate::RadixKey;
+
+// 面向性能的LSD基数排序，键可以是任意RadixKey
+// 和radix_sort_by_key相比：
+// 1. 键看成BYTES * 8位的大端整数，数位用移位和掩码从字节里取，可以跨字节，不限于8位
+// 2. 所有轮的直方图在排序前一次扫描全部算好，每轮只需做前缀和与分配
+// 3. 只分配一个和输入等长的缓冲区，在输入和缓冲区之间来回倒，不再每轮新建Vec
+// 4. 某一轮所有元素的数位都相同时（直方图只有一个非零桶）跳过这一轮
+// 8位数位每轮256个桶，u64要8轮；11位数位每轮2048个桶，只要6轮，但桶多了缓存不友好，
+// 一般元素很多时11位更快
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigitBits {
+    #[default]
+    Eight,
+    Eleven,
+}
+
+impl DigitBits {
+    fn bits(self) -> usize {
+        match self {
+            DigitBits::Eight => 8,
+            DigitBits::Eleven => 11,
+        }
+    }
+}
+
+pub fn lsd_radix_sort<K>(slice: &mut [K], digit_bits: DigitBits)
+    where K: RadixKey + Copy
+{
+    lsd_radix_sort_by_key(slice, digit_bits, |&e| e);
+}
+
+// 按key稳定排序，元素要求Copy，在两块内存之间来回复制
+pub fn lsd_radix_sort_by_key<T, K, F>(slice: &mut [T], digit_bits: DigitBits, key: F)
+    where T: Copy, K: RadixKey, F: Fn(&T) -> K
+{
+    let len = slice.len();
+    if len < 2 {
+        return;
+    }
+
+    let bits = digit_bits.bits();
+    let radix = 1 << bits;
+    let passes = (K::BYTES * 8).div_ceil(bits);
+
+    // 第p轮的直方图是counts[p * radix..(p + 1) * radix]
+    let mut counts = vec![0; passes * radix];
+    for e_ref in slice.iter() {
+        let k = key(e_ref);
+        for p in 0..passes {
+            counts[p * radix + digit(&k, p * bits, bits)] += 1;
+        }
+    }
+
+    let mut buffer = slice.to_vec();
+    let mut src: &mut [T] = slice;
+    let mut dst: &mut [T] = &mut buffer;
+    let mut in_buffer = false;
+    for p in 0..passes {
+        let count = &mut counts[p * radix..(p + 1) * radix];
+        let shift = p * bits;
+        if count[digit(&key(&src[0]), shift, bits)] == len {
+            continue;
+        }
+
+        // 前缀和变成每个桶的起始位置
+        let mut start = 0;
+        for c in count.iter_mut() {
+            let n = *c;
+            *c = start;
+            start += n;
+        }
+        for e_ref in src.iter() {
+            let digit = digit(&key(e_ref), shift, bits);
+            dst[count[digit]] = *e_ref;
+            count[digit] += 1;
+        }
+        mem::swap(&mut src, &mut dst);
+        in_buffer = !in_buffer;
+    }
+
+    // 最后一轮写进了缓冲区，复制回去
+    if in_buffer {
+        dst.copy_from_slice(src);
+    }
+}
+
+// 间接排序，返回排序后每个位置上元素的原下标
+// 排的是下标，by_key版本的元素不必是Copy，键仍要每轮现算
+pub fn arg_lsd_radix_sort<K>(slice: &[K], digit_bits: DigitBits) -> Vec<usize>
+    where K: RadixKey + Copy
+{
+    arg_lsd_radix_sort_by_key(slice, digit_bits, |&e| e)
+}
+
+pub fn arg_lsd_radix_sort_by_key<T, K, F>(slice: &[T], digit_bits: DigitBits, key: F) -> Vec<usize>
+    where K: RadixKey, F: Fn(&T) -> K
+{
+    let mut perm: Vec<usize> = (0..slice.len()).collect();
+    lsd_radix_sort_by_key(&mut perm, digit_bits, |&i| key(&slice[i]));
+    perm
+}
+
+// 从最低位数起，第shift位开始的bits位，超出键的高位当作0
+// 第b位在倒数第b / 8个字节里，一个数位最多跨三个字节
+fn digit<K: RadixKey>(key: &K, shift: usize, bits: usize) -> usize {
+    let mut digit = 0;
+    let mut taken = 0;
+    while taken < bits && shift + taken < K::BYTES * 8 {
+        let bit = shift + taken;
+        let byte = key.byte(K::BYTES - 1 - bit / 8) as usize;
+        let offset = bit % 8;
+        let n = (8 - offset).min(bits - taken);
+        digit |= ((byte >> offset) & ((1 << n) - 1)) << taken;
+        taken += n;
+    }
+    digit
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::random::XorShift64;
+
+    #[test]
+    fn it_sort_u64() {
+        let mut random = XorShift64::new(39);
+        let v: Vec<u64> = (0..10_000)
+            .map(|i| if i % 3 == 0 { random.next_u64() } else { random.next_u64() >> 40 })
+            .collect();
+        let mut expected = v.clone();
+        expected.sort();
+        for digit_bits in [DigitBits::Eight, DigitBits::Eleven] {
+            let mut sorted = v.clone();
+            lsd_radix_sort(&mut sorted, digit_bits);
+            assert_eq!(sorted, expected);
+        }
+
+        let mut extremes = vec![u64::MAX, 0, 1 << 63, u64::MAX, 1];
+        lsd_radix_sort(&mut extremes, DigitBits::Eleven);
+        assert_eq!(extremes, vec![0, 1, 1 << 63, u64::MAX, u64::MAX]);
+    }
+
+    #[test]
+    fn it_sort_by_key_stable_with_skipped_passes() {
+        // 键只有第3个字节不同，只做一轮，结果在缓冲区里，要复制回来
+        let mut v: Vec<(u64, usize)> = (0..1000)
+            .map(|i| (((((i * 7) % 10) as u64) << 16) | 0xab, i))
+            .collect();
+        lsd_radix_sort_by_key(&mut v, DigitBits::Eight, |e| e.0);
+        assert!(v.windows(2).all(|w| w[0] < w[1]));
+
+        // 全部相同，一轮都不做
+        let mut same = vec![(5u64, 'a'), (5, 'b'), (5, 'c')];
+        lsd_radix_sort_by_key(&mut same, DigitBits::Eleven, |e| e.0);
+        assert_eq!(same, vec![(5, 'a'), (5, 'b'), (5, 'c')]);
+    }
+
+    #[test]
+    fn it_arg_sort() {
+        let mut random = XorShift64::new(41);
+        let v: Vec<u64> = (0..2000).map(|_| random.next_u64() >> random.below(64)).collect();
+        let names: Vec<String> = v.iter().map(|e| e.to_string()).collect();
+        for digit_bits in [DigitBits::Eight, DigitBits::Eleven] {
+            let perm = arg_lsd_radix_sort(&v, digit_bits);
+            assert!(perm.windows(2).all(|w| (v[w[0]], w[0]) < (v[w[1]], w[1])));
+            // 元素是String，不是Copy，也能按键排下标
+            let by_key = arg_lsd_radix_sort_by_key(&names, digit_bits, |s| s.len() as u8);
+            let rank = |i: usize| (names[i].len(), i);
+            assert!(by_key.windows(2).all(|w| rank(w[0]) < rank(w[1])));
+        }
+    }
+
+    #[test]
+    fn it_sort_any_radix_key() {
+        // 11位的数位跨字节，最高一轮只剩下键的几个高位
+        let mut random = XorShift64::new(390);
+        let ints: Vec<i32> = (0..5000)
+            .map(|_| random.next_u64() as i32 >> random.below(32))
+            .collect();
+        let floats: Vec<f64> = ints.iter().map(|&i| (i as f64) / 7.0).collect();
+        let pairs: Vec<(char, i16)> = ints
+            .iter()
+            .map(|&i| (char::from(b'a' + ((i as u32) % 26) as u8), (i >> 16) as i16))
+            .collect();
+        for digit_bits in [DigitBits::Eight, DigitBits::Eleven] {
+            let mut sorted = ints.clone();
+            lsd_radix_sort(&mut sorted, digit_bits);
+            let mut expected = ints.clone();
+            expected.sort();
+            assert_eq!(sorted, expected);
+
+            let mut sorted = floats.clone();
+            lsd_radix_sort(&mut sorted, digit_bits);
+            let mut expected = floats.clone();
+            expected.sort_by(f64::total_cmp);
+            assert_eq!(sorted, expected);
+
+            let mut sorted: Vec<((char, i16), usize)> = pairs.iter().copied().zip(0..).collect();
+            lsd_radix_sort_by_key(&mut sorted, digit_bits, |e| e.0);
+            let mut expected = sorted.clone();
+            expected.sort();
+            assert_eq!(sorted, expected);
+        }
+    }
+}
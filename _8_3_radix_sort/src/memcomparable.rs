@@ -0,0 +1,359 @@
+// 保序的键编码（memcomparable）
+// 把由多个字段组成的键编码成一个字节串，使字节串的字典序和键按字段依次比较的顺序一致，
+// 这样复合键也能直接用msd_radix_sort这样的字节排序，或者直接比较字节串
+//
+// 每个字段的编码都是无前缀的（prefix-free）：任何一个值的编码都不是另一个值的编码的前缀，
+// 所以两个键第一个不同的字节一定落在同一个字段里，前面相同的字段长度也一样
+// 降序的字段把编码的每个字节按位取反，无前缀保证了取反后顺序正好反过来
+//
+// 整数：定长大端序，有符号数翻转符号位
+// 浮点数：和RadixKey一样的变换，顺序同total_cmp
+// 字节串：0x00转义为0x00 0xFF，末尾加0x00 0x01，比任何后续内容都小，所以前缀排在前面
+// bool：一个字节；Option：None为0x00，Some为0x01后接值，None排在前面
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+// 可以保序编码的类型
+// encode总是按升序写入，降序由KeyEncoder取反；decode从reader中读，reader负责取反
+pub trait Memcomparable: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    fn decode(reader: &mut KeyReader) -> Result<Self, &'static str>;
+}
+
+pub struct KeyEncoder {
+    bytes: Vec<u8>,
+}
+
+impl KeyEncoder {
+    pub fn new() -> KeyEncoder {
+        KeyEncoder { bytes: vec![] }
+    }
+
+    // 追加一个字段
+    pub fn push<V: Memcomparable>(&mut self, value: &V, direction: Direction) -> &mut KeyEncoder {
+        let start = self.bytes.len();
+        value.encode(&mut self.bytes);
+        if direction == Direction::Descending {
+            for b in &mut self.bytes[start..] {
+                *b = !*b;
+            }
+        }
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl Default for KeyEncoder {
+    fn default() -> Self {
+        KeyEncoder::new()
+    }
+}
+
+// 按编码时的顺序和方向依次取出字段，调试时用来还原键
+pub struct KeyDecoder<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> KeyDecoder<'a> {
+    pub fn new(input: &'a [u8]) -> KeyDecoder<'a> {
+        KeyDecoder { input }
+    }
+
+    pub fn pop<V: Memcomparable>(&mut self, direction: Direction) -> Result<V, &'static str> {
+        let mut reader = KeyReader {
+            input: self.input,
+            invert: direction == Direction::Descending,
+        };
+        let value = V::decode(&mut reader)?;
+        self.input = reader.input;
+        Ok(value)
+    }
+
+    // 所有字段都取完了
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+}
+
+pub struct KeyReader<'a> {
+    input: &'a [u8],
+    invert: bool,
+}
+
+impl<'a> KeyReader<'a> {
+    pub fn next_byte(&mut self) -> Result<u8, &'static str> {
+        match self.input.split_first() {
+            Some((&b, rest)) => {
+                self.input = rest;
+                Ok(if self.invert { !b } else { b })
+            }
+            None => Err("Unexpected end of key"),
+        }
+    }
+
+    pub fn next_bytes<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        let mut bytes = [0; N];
+        for b in &mut bytes {
+            *b = self.next_byte()?;
+        }
+        Ok(bytes)
+    }
+}
+
+macro_rules! impl_memcomparable_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Memcomparable for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+                    Ok(<$t>::from_be_bytes(reader.next_bytes()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_memcomparable_unsigned!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_memcomparable_signed {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl Memcomparable for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    ((*self as $u) ^ (1 << (<$u>::BITS - 1))).encode(out);
+                }
+
+                fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+                    Ok((<$u>::decode(reader)? ^ (1 << (<$u>::BITS - 1))) as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_memcomparable_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+macro_rules! impl_memcomparable_float {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl Memcomparable for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    let bits = self.to_bits();
+                    let sign = 1 << (<$u>::BITS - 1);
+                    let ordered = if bits & sign == 0 { bits ^ sign } else { !bits };
+                    ordered.encode(out);
+                }
+
+                fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+                    let ordered = <$u>::decode(reader)?;
+                    let sign = 1 << (<$u>::BITS - 1);
+                    let bits = if ordered & sign != 0 { ordered ^ sign } else { !ordered };
+                    Ok(<$t>::from_bits(bits))
+                }
+            }
+        )*
+    };
+}
+
+impl_memcomparable_float!(f32 => u32, f64 => u64);
+
+impl Memcomparable for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+        match reader.next_byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Invalid bool"),
+        }
+    }
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    for &b in bytes {
+        out.push(b);
+        if b == 0 {
+            out.push(0xff);
+        }
+    }
+    out.extend_from_slice(&[0x00, 0x01]);
+}
+
+impl Memcomparable for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_bytes(self, out);
+    }
+
+    fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+        let mut bytes = vec![];
+        loop {
+            match reader.next_byte()? {
+                0 =>
+                    match reader.next_byte()? {
+                        0xff => bytes.push(0),
+                        0x01 => {
+                            return Ok(bytes);
+                        }
+                        _ => {
+                            return Err("Invalid escape");
+                        }
+                    }
+                b => bytes.push(b),
+            }
+        }
+    }
+}
+
+// 和字节串一样编码，UTF-8的字节序就是码位的顺序
+impl Memcomparable for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        encode_bytes(self.as_bytes(), out);
+    }
+
+    fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+        String::from_utf8(Vec::decode(reader)?).map_err(|_| "Invalid UTF-8")
+    }
+}
+
+impl<V: Memcomparable> Memcomparable for Option<V> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(value) => {
+                out.push(1);
+                value.encode(out);
+            }
+        }
+    }
+
+    fn decode(reader: &mut KeyReader) -> Result<Self, &'static str> {
+        match reader.next_byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(V::decode(reader)?)),
+            _ => Err("Invalid option marker"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::msd_radix_sort_by_key;
+    use algorithms_prelude::random::XorShift64;
+    use std::cmp::Reverse;
+
+    use Direction::{ Ascending, Descending };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Record {
+        country: String,
+        score: i32,
+        timestamp: i64,
+        key: Vec<u8>,
+    }
+
+    fn encode(country: &String, score: i32, timestamp: i64) -> Vec<u8> {
+        let mut encoder = KeyEncoder::new();
+        encoder.push(country, Ascending).push(&score, Descending).push(&timestamp, Ascending);
+        encoder.into_bytes()
+    }
+
+    #[test]
+    fn it_sort_composite_keys_as_bytes() {
+        // (国家升序，分数降序，时间戳升序)
+        let countries = ["", "CN", "CN\0", "C", "US", "DE"];
+        let mut random = XorShift64::new(41);
+        let mut v: Vec<Record> = (0..3000)
+            .map(|_| {
+                let country = countries[random.below(countries.len())].to_string();
+                let score = (random.below(7) as i32) - 3;
+                let timestamp = (random.next_u64() as i64) >> random.below(64);
+                let key = encode(&country, score, timestamp);
+                Record { country, score, timestamp, key }
+            })
+            .collect();
+        let mut expected = v.clone();
+        expected.sort_by_key(|e| (e.country.clone(), Reverse(e.score), e.timestamp));
+
+        msd_radix_sort_by_key(&mut v, |e: &Record| e.key.as_slice());
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn it_decode_round_trip() -> Result<(), &'static str> {
+        let mut encoder = KeyEncoder::new();
+        encoder
+            .push(&"a\0b".to_string(), Descending)
+            .push(&-0.0f64, Ascending)
+            .push(&Some(i64::MIN), Descending)
+            .push(&None::<u16>, Ascending)
+            .push(&true, Descending)
+            .push(&vec![0u8, 0, 0xff], Ascending);
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = KeyDecoder::new(&bytes);
+        assert_eq!(decoder.pop::<String>(Descending)?, "a\0b");
+        assert!(decoder.pop::<f64>(Ascending)?.is_sign_negative());
+        assert_eq!(decoder.pop::<Option<i64>>(Descending)?, Some(i64::MIN));
+        assert_eq!(decoder.pop::<Option<u16>>(Ascending)?, None);
+        assert!(decoder.pop::<bool>(Descending)?);
+        assert_eq!(decoder.pop::<Vec<u8>>(Ascending)?, vec![0, 0, 0xff]);
+        assert!(decoder.is_empty());
+        assert_eq!(decoder.pop::<u8>(Ascending), Err("Unexpected end of key"));
+
+        // 类型不对或者截断的输入
+        let bytes = {
+            let mut encoder = KeyEncoder::new();
+            encoder.push(&"ab".to_string(), Ascending);
+            encoder.into_bytes()
+        };
+        assert_eq!(
+            KeyDecoder::new(&bytes).pop::<Option<u8>>(Ascending),
+            Err("Invalid option marker")
+        );
+        assert_eq!(
+            KeyDecoder::new(&[0x61, 0x00, 0x02]).pop::<String>(Ascending),
+            Err("Invalid escape")
+        );
+        assert_eq!(
+            KeyDecoder::new(&bytes[..3]).pop::<String>(Ascending),
+            Err("Unexpected end of key")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_preserve_order() {
+        fn bytes<V: Memcomparable>(value: &V, direction: Direction) -> Vec<u8> {
+            let mut encoder = KeyEncoder::new();
+            encoder.push(value, direction);
+            encoder.into_bytes()
+        }
+
+        let floats = [f32::NEG_INFINITY, -1.0, -0.0, 0.0, 1e-40, 2.5, f32::INFINITY, f32::NAN];
+        assert!(floats.windows(2).all(|w| bytes(&w[0], Ascending) < bytes(&w[1], Ascending)));
+        assert!(floats.windows(2).all(|w| bytes(&w[0], Descending) > bytes(&w[1], Descending)));
+
+        // 前缀排在前面，降序时反过来
+        let strings = ["", "\0", "\0\0", "\x01", "a", "a\0", "ab", "b"].map(String::from);
+        assert!(strings.windows(2).all(|w| bytes(&w[0], Ascending) < bytes(&w[1], Ascending)));
+        assert!(strings.windows(2).all(|w| bytes(&w[0], Descending) > bytes(&w[1], Descending)));
+
+        let options = [None, Some(i8::MIN), Some(0), Some(i8::MAX)];
+        assert!(options.windows(2).all(|w| bytes(&w[0], Ascending) < bytes(&w[1], Ascending)));
+    }
+}
@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+
+use algorithms_prelude::{ permutation::apply_permutation, CompareSorter };
+use issort::InsertionSorter;
+
+// 不定长字符串的排序（思考题8-3 b）
+// 键是字节串，按字典序排序，字符串结束视为比任何字节都小的符号，所以前缀排在前面
+
+// 桶不超过这么多元素时改用插入排序，256个桶的计数数组对小桶来说太浪费
+const INSERTION_THRESHOLD: usize = 16;
+
+// MSD基数排序
+// 从第0个字节开始，按当前字节把区间分到257个桶里（第0个桶是已经结束的字符串），
+// 每个桶再按下一个字节继续分，已结束的桶里的字符串都相等，不用再分
+// 每个字节至多被检查常数次，总时间是O(字符总数 + 桶的个数 * 257)
+// 每轮分配都是稳定的，小桶的插入排序也是稳定的，所以整个排序稳定
+//
+// 实际排序的是（键，原下标）对，它们可以按位复制，分配时来回倒也便宜，
+// 排好以后再用apply_permutation按环一次把元素放到位
+// 待分的区间放在显式栈里，字符串再长也不会爆栈
+pub fn msd_radix_sort<S: AsRef<[u8]>>(slice: &mut [S]) {
+    msd_radix_sort_by_key(slice, |s| s.as_ref());
+}
+
+pub fn msd_radix_sort_by_key<T, F>(slice: &mut [T], key: F)
+    where F: for<'a> Fn(&'a T) -> &'a [u8]
+{
+    let perm = arg_msd_radix_sort_by_key(slice, key);
+    apply_permutation(slice, &perm).unwrap();
+}
+
+// 间接排序，返回排序后每个位置上元素的原下标
+pub fn arg_msd_radix_sort<S: AsRef<[u8]>>(slice: &[S]) -> Vec<usize> {
+    arg_msd_radix_sort_by_key(slice, |s| s.as_ref())
+}
+
+pub fn arg_msd_radix_sort_by_key<T, F>(slice: &[T], key: F) -> Vec<usize>
+    where F: for<'a> Fn(&'a T) -> &'a [u8]
+{
+    if slice.len() < 2 {
+        return (0..slice.len()).collect();
+    }
+
+    let mut entries: Vec<(&[u8], usize)> = slice
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (key(e), i))
+        .collect();
+    let mut aux = entries.clone();
+    let mut stack = vec![(0, entries.len(), 0)];
+    while let Some((first, end, depth)) = stack.pop() {
+        let bucket = &mut entries[first..end];
+        if bucket.len() <= INSERTION_THRESHOLD {
+            // 桶里的字符串前depth个字节都相同，比较时要跳过它们，否则公共前缀很长时
+            // 每次比较都是O(depth)，总时间就不再是O(字符总数)
+            // sort_by只接受函数指针，带不进depth，所以先把键截成从第depth个字节开始
+            for e in bucket.iter_mut() {
+                e.0 = &e.0[depth..];
+            }
+            InsertionSorter(bucket).sort_by(|prev, next| prev.0 <= next.0);
+            continue;
+        }
+
+        let mut count = [0; 258]; // count[c + 1]统计符号c，前缀和之后count[c]是符号c的起始位置
+        for e in bucket.iter() {
+            count[symbol(e.0, depth) + 1] += 1;
+        }
+        for c in 1..count.len() {
+            count[c] += count[c - 1];
+        }
+        let aux = &mut aux[first..end];
+        for e in bucket.iter() {
+            let c = symbol(e.0, depth);
+            aux[count[c]] = *e;
+            count[c] += 1;
+        }
+        bucket.copy_from_slice(aux);
+
+        // 分配之后count[c]是符号c的结束位置，跳过已结束的桶
+        for c in 1..257 {
+            let (bucket_first, bucket_end) = (count[c - 1], count[c]);
+            if bucket_end - bucket_first > 1 {
+                stack.push((first + bucket_first, first + bucket_end, depth + 1));
+            }
+        }
+    }
+
+    entries
+        .iter()
+        .map(|e| e.1)
+        .collect()
+}
+
+// 第depth个符号，结束为0，字节b为b + 1
+fn symbol(key: &[u8], depth: usize) -> usize {
+    key.get(depth).map_or(0, |&b| (b as usize) + 1)
+}
+
+// 多键快速排序（Bentley & Sedgewick），三路划分的字符串快速排序
+// 按第depth个符号把区间分成小于、等于、大于主元三段，
+// 小于和大于的两段继续按第depth个符号排，等于的一段按第depth + 1个符号排
+// 不需要额外的数组，原地排序，不稳定
+// 只对较短的两段递归，最长的一段循环，每次递归的区间至多是原来的一半，栈深度O(lgn)
+pub fn multikey_quick_sort<S: AsRef<[u8]>>(slice: &mut [S]) {
+    let len = slice.len();
+    multikey(slice, 0, len, 0);
+}
+
+// 间接排序，返回下标的排列，不稳定
+pub fn arg_multikey_quick_sort<S: AsRef<[u8]>>(slice: &[S]) -> Vec<usize> {
+    let mut entries: Vec<Entry> = slice
+        .iter()
+        .enumerate()
+        .map(|(i, s)| Entry(s.as_ref(), i))
+        .collect();
+    multikey_quick_sort(&mut entries);
+    entries
+        .iter()
+        .map(|e| e.1)
+        .collect()
+}
+
+// （键，原下标）对，multikey_quick_sort只按键排
+struct Entry<'a>(&'a [u8], usize);
+
+impl<'a> AsRef<[u8]> for Entry<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+fn multikey<S: AsRef<[u8]>>(slice: &mut [S], mut first: usize, mut end: usize, mut depth: usize) {
+    loop {
+        if end - first <= INSERTION_THRESHOLD {
+            insertion_sort_from(&mut slice[first..end], depth);
+            return;
+        }
+
+        // 三数取中
+        let mid = first + (end - first) / 2;
+        let mut samples = [
+            symbol(slice[first].as_ref(), depth),
+            symbol(slice[mid].as_ref(), depth),
+            symbol(slice[end - 1].as_ref(), depth),
+        ];
+        samples.sort_unstable();
+        let pivot = samples[1];
+
+        let (mut lt, mut i, mut gt) = (first, first, end);
+        while i < gt {
+            match symbol(slice[i].as_ref(), depth).cmp(&pivot) {
+                Ordering::Less => {
+                    slice.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    slice.swap(i, gt);
+                }
+                Ordering::Equal => {
+                    i += 1;
+                }
+            }
+        }
+
+        // 主元是结束符时，等于的一段都是相同的字符串，不用再排
+        let equal_depth = if pivot == 0 { None } else { Some(depth + 1) };
+        let mut parts = [(first, lt, Some(depth)), (lt, gt, equal_depth), (gt, end, Some(depth))];
+        parts.sort_unstable_by_key(|&(part_first, part_end, _)| part_end - part_first);
+        for &(part_first, part_end, part_depth) in &parts[..2] {
+            if let Some(part_depth) = part_depth {
+                multikey(slice, part_first, part_end, part_depth);
+            }
+        }
+        match parts[2] {
+            (part_first, part_end, Some(part_depth)) => {
+                (first, end, depth) = (part_first, part_end, part_depth);
+            }
+            _ => {
+                return;
+            }
+        }
+    }
+}
+
+// 区间里的字符串前depth个字节都相同，插入排序只从第depth个字节开始比较
+// 和MSD的小桶一样，跳过公共前缀才能保证总时间O(字符总数)
+fn insertion_sort_from<S: AsRef<[u8]>>(slice: &mut [S], depth: usize) {
+    for i in 1..slice.len() {
+        let mut j = i;
+        while j > 0 && slice[j - 1].as_ref()[depth..] > slice[j].as_ref()[depth..] {
+            slice.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::permutation;
+    use algorithms_prelude::{ random::XorShift64, stack::{ run_with_stack, SMALL_STACK } };
+
+    // 随机的路径，只用少数几个目录名，所以有大量公共前缀
+    fn random_paths(len: usize, seed: u64) -> Vec<String> {
+        let parts = ["usr", "lib", "local", "bin", "a", "", "rust", "l"];
+        let mut random = XorShift64::new(seed);
+        (0..len)
+            .map(|_| {
+                let depth = random.below(6);
+                (0..depth)
+                    .map(|_| parts[random.below(parts.len())])
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_sort_strings() {
+        let mut v = vec!["she", "sells", "seashells", "by", "the", "sea", "shore", "", "s", "she"];
+        let mut expected = v.clone();
+        expected.sort();
+        let mut multikey = v.clone();
+        msd_radix_sort(&mut v);
+        assert_eq!(v, expected);
+        multikey_quick_sort(&mut multikey);
+        assert_eq!(multikey, expected);
+
+        let mut paths = random_paths(10_000, 40);
+        let mut expected = paths.clone();
+        expected.sort();
+        let mut multikey = paths.clone();
+        msd_radix_sort(&mut paths);
+        assert_eq!(paths, expected);
+        multikey_quick_sort(&mut multikey);
+        assert_eq!(multikey, expected);
+    }
+
+    #[test]
+    fn it_sort_by_key_stable() {
+        #[derive(Debug, PartialEq)]
+        struct File {
+            path: Vec<u8>,
+            id: usize,
+        }
+
+        let paths = random_paths(2000, 8);
+        let mut v: Vec<File> = paths
+            .iter()
+            .enumerate()
+            .map(|(id, path)| File { path: path.clone().into_bytes(), id })
+            .collect();
+        msd_radix_sort_by_key(&mut v, |e: &File| e.path.as_slice());
+        assert!(v.windows(2).all(|w| (&w[0].path, w[0].id) < (&w[1].path, w[1].id)));
+    }
+
+    #[test]
+    fn it_arg_sort_strings() {
+        let paths = random_paths(3000, 9);
+        let msd = arg_msd_radix_sort(&paths);
+        // 稳定，相同的路径下标升序
+        assert!(msd.windows(2).all(|w| (&paths[w[0]], w[0]) < (&paths[w[1]], w[1])));
+        let by_key = arg_msd_radix_sort_by_key(&paths, |s| s.as_bytes());
+        assert_eq!(by_key, msd);
+        let multikey = arg_multikey_quick_sort(&paths);
+        assert!(multikey.windows(2).all(|w| paths[w[0]] <= paths[w[1]]));
+        assert!(permutation::is_permutation(&multikey));
+        assert_eq!(arg_msd_radix_sort::<&str>(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn it_sort_long_common_prefix_with_small_stack() {
+        // 公共前缀很长，按字节逐层递归的写法会有上万层
+        let prefix = "x".repeat(20_000);
+        let mut random = XorShift64::new(3);
+        let v: Vec<String> = (0..200).map(|_| format!("{}{}", prefix, random.below(50))).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        let mut msd = v.clone();
+        let mut multikey = v;
+        run_with_stack(SMALL_STACK, || {
+            msd_radix_sort(&mut msd);
+            multikey_quick_sort(&mut multikey);
+        });
+        assert_eq!(msd, expected);
+        assert_eq!(multikey, expected);
+    }
+
+    #[test]
+    fn it_sort_small_buckets_past_shared_prefix() {
+        // 不到INSERTION_THRESHOLD个字符串，分过几层以后直接进插入排序，
+        // 这时只比较第depth个字节以后的部分，有的字符串正好在公共前缀处结束
+        let v: Vec<String> = ["abcz", "abc", "abcb", "abd", "abca", "ab", "abcb", "abcab"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut expected = v.clone();
+        expected.sort();
+        let mut msd = v.clone();
+        msd_radix_sort(&mut msd);
+        assert_eq!(msd, expected);
+        let mut multikey = v;
+        multikey_quick_sort(&mut multikey);
+        assert_eq!(multikey, expected);
+    }
+}
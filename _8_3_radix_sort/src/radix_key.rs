@@ -0,0 +1,157 @@
+// 可以做基数排序的键
+// 把键看成定长的字节串，字节串的字典序要和键的大小顺序一致，
+// 基数排序就只需要按字节从低到高做BYTES轮计数排序，每轮256个桶
+pub trait RadixKey {
+    // 字节数，同一类型的键必须相同
+    const BYTES: usize;
+
+    // 第i个字节，i = 0是最高位
+    fn byte(&self, i: usize) -> u8;
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const BYTES: usize = std::mem::size_of::<$t>();
+
+                fn byte(&self, i: usize) -> u8 {
+                    (*self >> ((Self::BYTES - 1 - i) * 8)) as u8
+                }
+            }
+        )*
+    };
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, u128, usize);
+
+// 补码的负数最高位是1，按无符号比较会排在正数后面，翻转符号位就对了
+macro_rules! impl_radix_key_signed {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const BYTES: usize = std::mem::size_of::<$t>();
+
+                fn byte(&self, i: usize) -> u8 {
+                    ((*self as $u) ^ (1 << (<$u>::BITS - 1))).byte(i)
+                }
+            }
+        )*
+    };
+}
+
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+// 浮点数是符号位加绝对值的表示：非负数翻转符号位，负数翻转所有位（绝对值越大越小）
+// 得到的顺序和total_cmp一致：-NaN < -∞ < ... < -0.0 < 0.0 < ... < ∞ < NaN
+macro_rules! impl_radix_key_float {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const BYTES: usize = std::mem::size_of::<$t>();
+
+                fn byte(&self, i: usize) -> u8 {
+                    let bits = self.to_bits();
+                    let sign = 1 << (<$u>::BITS - 1);
+                    let ordered = if bits & sign == 0 { bits ^ sign } else { !bits };
+                    ordered.byte(i)
+                }
+            }
+        )*
+    };
+}
+
+impl_radix_key_float!(f32 => u32, f64 => u64);
+
+// 码位最大是0x10FFFF，三个字节就够了，少排一轮
+impl RadixKey for char {
+    const BYTES: usize = 3;
+
+    fn byte(&self, i: usize) -> u8 {
+        (*self as u32).byte(i + 1)
+    }
+}
+
+impl RadixKey for bool {
+    const BYTES: usize = 1;
+
+    fn byte(&self, _: usize) -> u8 {
+        *self as u8
+    }
+}
+
+// 数组按元素的字典序
+impl<K: RadixKey, const N: usize> RadixKey for [K; N] {
+    const BYTES: usize = N * K::BYTES;
+
+    fn byte(&self, i: usize) -> u8 {
+        self[i / K::BYTES].byte(i % K::BYTES)
+    }
+}
+
+// 元组按分量的字典序，前面的分量在高位
+macro_rules! impl_radix_key_tuple {
+    ($(($($name:ident . $index:tt),+)),*) => {
+        $(
+            impl<$($name: RadixKey),+> RadixKey for ($($name,)+) {
+                const BYTES: usize = 0 $(+ $name::BYTES)+;
+
+                #[allow(unused_assignments)]
+                fn byte(&self, i: usize) -> u8 {
+                    let mut i = i;
+                    $(
+                        if i < $name::BYTES {
+                            return self.$index.byte(i);
+                        }
+                        i -= $name::BYTES;
+                    )+
+                    unreachable!("字节下标越界")
+                }
+            }
+        )*
+    };
+}
+
+impl_radix_key_tuple!((A.0, B.1), (A.0, B.1, C.2), (A.0, B.1, C.2, D.3));
+
+impl<K: RadixKey + ?Sized> RadixKey for &K {
+    const BYTES: usize = K::BYTES;
+
+    fn byte(&self, i: usize) -> u8 {
+        (**self).byte(i)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bytes<K: RadixKey>(key: K) -> Vec<u8> {
+        (0..K::BYTES).map(|i| key.byte(i)).collect()
+    }
+
+    #[test]
+    fn it_preserve_order_as_bytes() {
+        let ints = [i64::MIN, -300, -1, 0, 1, 255, 256, i64::MAX];
+        assert!(ints.windows(2).all(|w| bytes(w[0]) < bytes(w[1])));
+
+        let floats = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1e300,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.5,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        assert!(floats.windows(2).all(|w| bytes(w[0]) < bytes(w[1])));
+
+        assert_eq!(<(u8, i16, [char; 2])>::BYTES, 1 + 2 + 6);
+        assert_eq!(bytes((1u8, -1i16)), vec![1, 0x7f, 0xff]);
+        assert_eq!(bytes(['a', '\u{10FFFF}']), vec![0, 0, 0x61, 0x10, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,62 @@
+// 比较不同桶实现在均匀和聚集输入上的耗时
+// 用cargo bench -p bucket_sort运行，不依赖第三方的基准测试框架，每项取几次中最快的一次
+use std::time::{ Duration, Instant };
+
+use algorithms_prelude::random::XorShift64;
+use bucket_sort::{ bucket_sort_with, Bucket, BucketStorage, RecursiveBucket, VecBucket };
+use issort::InsertionSorter;
+use merge_sort::MergeSorter;
+use quick_sort::IntroSorter;
+
+const LEN: usize = 20_000;
+const ROUNDS: usize = 5;
+
+type Measure = fn(&[f64]) -> Duration;
+type Items = Vec<(f64, f64)>;
+
+fn measure<B: BucketStorage<f64>>(input: &[f64]) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let v = input.to_vec();
+            let start = Instant::now();
+            let result = bucket_sort_with::<B, _, _>(v, |e| *e).unwrap();
+            let elapsed = start.elapsed();
+            assert!(result.windows(2).all(|w| w[0] <= w[1]));
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let mut random = XorShift64::new(43);
+    let uniform: Vec<f64> = (0..LEN).map(|_| random.next_f64()).collect();
+    // u^8大部分落在0附近，前几个桶里挤了大量元素
+    let skewed: Vec<f64> = uniform
+        .iter()
+        .map(|u| u.powi(8))
+        .collect();
+    // 全部挤在第一个桶里
+    let clustered: Vec<f64> = uniform
+        .iter()
+        .map(|u| u / (LEN as f64))
+        .collect();
+
+    println!("{:<28}{:>14}{:>14}{:>14}", "bucket", "uniform", "skewed", "clustered");
+    let rows: [(&str, Measure); 5] = [
+        ("Bucket (linked list)", measure::<Bucket<f64>>),
+        ("VecBucket + Insertion", measure::<VecBucket<f64, InsertionSorter<Items>>>),
+        ("VecBucket + Merge", measure::<VecBucket<f64, MergeSorter<Items>>>),
+        ("VecBucket + Intro", measure::<VecBucket<f64, IntroSorter<Items>>>),
+        ("RecursiveBucket", measure::<RecursiveBucket<f64>>),
+    ];
+    for (name, measure) in rows {
+        println!(
+            "{:<28}{:>14?}{:>14?}{:>14?}",
+            name,
+            measure(&uniform),
+            measure(&skewed),
+            measure(&clustered)
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::{ error::Error, fmt };
+
+use crate::{ linked_list_bucket::Bucket, storage::BucketStorage };
+
+// 任意范围的桶排序
+// bucket_sort要求映射到[0, 1)，这里的键可以在任意区间[lo, hi]，
+// 区间可以给定，也可以先扫描一遍求出最小值和最大值
+// 桶的个数默认和元素个数相同，也可以指定：桶少了每个桶里的插入排序更慢，桶多了占内存，
+// 指定的个数超过有限键的个数时按键的个数建桶，免得一个很大的数把内存耗尽
+// 键在区间里均匀分布时期望O(n)
+//
+// NaN和无穷大没法按比例分到桶里，所以单独处理：
+// NaN按NanPolicy拒绝、排在最前或者排在最后
+// 无穷大按InfinityPolicy拒绝、负无穷排在最前正无穷排在最后、全部排在最前或者全部排在最后，
+// 都在NaN之内，放在一起时负无穷在正无穷前面
+// 同一类的元素保持原来的先后，桶里的插入也是稳定的，所以整个排序稳定
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    #[default]
+    Reject,
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InfinityPolicy {
+    #[default]
+    Reject,
+    // 负无穷在最前，正无穷在最后
+    Ends,
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketSortError {
+    // 给定的区间端点不是有限数，或者lo > hi
+    InvalidRange,
+    ZeroBuckets,
+    // 有限的键不在给定的区间内
+    KeyOutOfRange,
+    NotANumber,
+    Infinite,
+}
+
+impl fmt::Display for BucketSortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            BucketSortError::InvalidRange => "区间无效",
+            BucketSortError::ZeroBuckets => "桶的个数为0",
+            BucketSortError::KeyOutOfRange => "元素值溢出",
+            BucketSortError::NotANumber => "键为NaN",
+            BucketSortError::Infinite => "键为无穷大",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for BucketSortError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BucketSorter {
+    range: Option<(f64, f64)>, // None时扫描求出
+    buckets: Option<usize>, // None时和有限键的个数相同
+    nan: NanPolicy,
+    infinity: InfinityPolicy,
+}
+
+impl BucketSorter {
+    pub fn new() -> BucketSorter {
+        BucketSorter::default()
+    }
+
+    pub fn range(mut self, lo: f64, hi: f64) -> BucketSorter {
+        self.range = Some((lo, hi));
+        self
+    }
+
+    pub fn buckets(mut self, buckets: usize) -> BucketSorter {
+        self.buckets = Some(buckets);
+        self
+    }
+
+    pub fn nan(mut self, nan: NanPolicy) -> BucketSorter {
+        self.nan = nan;
+        self
+    }
+
+    pub fn infinity(mut self, infinity: InfinityPolicy) -> BucketSorter {
+        self.infinity = infinity;
+        self
+    }
+
+    // 用链表桶排序
+    pub fn sort<T, F>(&self, arr: Vec<T>, mapper: F) -> Result<Vec<T>, BucketSortError>
+        where F: Fn(&T) -> f64
+    {
+        self.sort_with::<Bucket<T>, T, F>(arr, mapper)
+    }
+
+    // 间接排序，返回排序后每个位置上元素的原下标，NaN和无穷大同样按策略处理
+    pub fn arg_sort<T, F>(&self, slice: &[T], mapper: F) -> Result<Vec<usize>, BucketSortError>
+        where F: Fn(&T) -> f64
+    {
+        self.sort((0..slice.len()).collect(), |&i| mapper(&slice[i]))
+    }
+
+    // 指定桶的实现，输入可能聚集时用VecBucket或者RecursiveBucket
+    pub fn sort_with<B, T, F>(&self, arr: Vec<T>, mapper: F) -> Result<Vec<T>, BucketSortError>
+        where B: BucketStorage<T>, F: Fn(&T) -> f64
+    {
+        if self.buckets == Some(0) {
+            return Err(BucketSortError::ZeroBuckets);
+        }
+        if let Some((lo, hi)) = self.range {
+            if !lo.is_finite() || !hi.is_finite() || lo > hi {
+                return Err(BucketSortError::InvalidRange);
+            }
+        }
+
+        // 先把NaN和无穷大分出去
+        let capacity = arr.len();
+        let mut nans = vec![];
+        let mut negative_infinities = vec![];
+        let mut positive_infinities = vec![];
+        let mut finite = Vec::with_capacity(arr.len());
+        for e in arr {
+            let key = mapper(&e);
+            if key.is_nan() {
+                if self.nan == NanPolicy::Reject {
+                    return Err(BucketSortError::NotANumber);
+                }
+                nans.push(e);
+            } else if key.is_infinite() {
+                if self.infinity == InfinityPolicy::Reject {
+                    return Err(BucketSortError::Infinite);
+                }
+                if key < 0.0 {
+                    negative_infinities.push(e);
+                } else {
+                    positive_infinities.push(e);
+                }
+            } else {
+                finite.push((key, e));
+            }
+        }
+
+        let (lo, hi) = match self.range {
+            Some((lo, hi)) => {
+                if finite.iter().any(|&(key, _)| key < lo || key > hi) {
+                    return Err(BucketSortError::KeyOutOfRange);
+                }
+                (lo, hi)
+            }
+            None =>
+                finite
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(key, _)| {
+                        (lo.min(key), hi.max(key))
+                    }),
+        };
+
+        // 进桶
+        let bucket_count = self.buckets.unwrap_or(finite.len()).clamp(1, finite.len().max(1));
+        let mut buckets: Vec<B> = Vec::with_capacity(bucket_count);
+        buckets.resize_with(bucket_count, B::new);
+        // 端点先各除以2再相减，lo和hi分别接近f64::MIN和f64::MAX时差也不会溢出
+        let half_width = hi / 2.0 - lo / 2.0;
+        for (key, e) in finite {
+            let bucket_id = if half_width > 0.0 {
+                // as对浮点数是饱和转换，key == hi时算出bucket_count，归到最后一个桶
+                let ratio = (key / 2.0 - lo / 2.0) / half_width;
+                ((ratio * (bucket_count as f64)) as usize).min(bucket_count - 1)
+            } else {
+                0
+            };
+            buckets[bucket_id].insert(key, e);
+        }
+
+        let (nans_first, nans_last) = match self.nan {
+            NanPolicy::Last => (vec![], nans),
+            _ => (nans, vec![]),
+        };
+        let (infinities_first, infinities_last) = match self.infinity {
+            InfinityPolicy::First => {
+                negative_infinities.extend(positive_infinities);
+                (negative_infinities, vec![])
+            }
+            InfinityPolicy::Last => {
+                negative_infinities.extend(positive_infinities);
+                (vec![], negative_infinities)
+            }
+            _ => (negative_infinities, positive_infinities),
+        };
+        let mut result = Vec::with_capacity(capacity);
+        result.extend(nans_first);
+        result.extend(infinities_first);
+        for bucket in buckets {
+            bucket.drain_sorted(&mut result);
+        }
+        result.extend(infinities_last);
+        result.extend(nans_last);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ RecursiveBucket, VecBucket };
+    use issort::InsertionSorter;
+    use merge_sort::MergeSorter;
+
+    #[test]
+    fn it_sort_arbitrary_range() -> Result<(), BucketSortError> {
+        let readings = vec![-40.5, 12.25, 85.0, -3.0, 12.25, 0.0, -40.5, 1e3];
+        let mut expected = readings.clone();
+        expected.sort_by(f64::total_cmp);
+
+        // 扫描求出区间
+        assert_eq!(BucketSorter::new().sort(readings.clone(), |e| *e)?, expected);
+        // 给定区间和桶的个数，只有一个桶时退化成插入排序
+        // 桶比键多时按键的个数建桶，usize::MAX也不会去分配内存
+        for buckets in [1, 3, 100, usize::MAX] {
+            let sorter = BucketSorter::new().range(-50.0, 1000.0).buckets(buckets);
+            assert_eq!(sorter.sort(readings.clone(), |e| *e)?, expected);
+        }
+
+        // 贴着f64的两端
+        let extremes = vec![f64::MAX, 0.0, f64::MIN, -1.0, f64::MAX];
+        let result = BucketSorter::new().sort(extremes, |e| *e)?;
+        assert_eq!(result, vec![f64::MIN, -1.0, 0.0, f64::MAX, f64::MAX]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reject_invalid_input() {
+        let v = vec![1.0, 2.0, 3.0];
+        assert_eq!(
+            BucketSorter::new().range(0.0, 2.5).sort(v.clone(), |e| *e),
+            Err(BucketSortError::KeyOutOfRange)
+        );
+        assert_eq!(
+            BucketSorter::new().range(2.0, 1.0).sort(v.clone(), |e| *e),
+            Err(BucketSortError::InvalidRange)
+        );
+        assert_eq!(
+            BucketSorter::new().range(0.0, f64::INFINITY).sort(v.clone(), |e| *e),
+            Err(BucketSortError::InvalidRange)
+        );
+        assert_eq!(
+            BucketSorter::new().buckets(0).sort(v.clone(), |e| *e),
+            Err(BucketSortError::ZeroBuckets)
+        );
+        assert_eq!(
+            BucketSorter::new().sort(vec![1.0, f64::NAN], |e| *e),
+            Err(BucketSortError::NotANumber)
+        );
+        assert_eq!(
+            BucketSorter::new().sort(vec![1.0, f64::NEG_INFINITY], |e| *e),
+            Err(BucketSortError::Infinite)
+        );
+    }
+
+    #[test]
+    fn it_place_non_finite_keys() -> Result<(), BucketSortError> {
+        let v = vec![
+            (2.0, "a"),
+            (f64::NAN, "b"),
+            (f64::INFINITY, "c"),
+            (-1.0, "d"),
+            (f64::NEG_INFINITY, "e"),
+            (f64::NAN, "f"),
+            (2.0, "g")
+        ];
+        let names = |result: Vec<(f64, &'static str)>| {
+            result
+                .into_iter()
+                .map(|e| e.1)
+                .collect::<Vec<_>>()
+        };
+
+        let sorter = BucketSorter::new().infinity(InfinityPolicy::Ends);
+        let result = sorter.nan(NanPolicy::First).sort(v.clone(), |e| e.0)?;
+        assert_eq!(names(result), vec!["b", "f", "e", "d", "a", "g", "c"]);
+        let result = sorter.nan(NanPolicy::Last).sort(v.clone(), |e| e.0)?;
+        assert_eq!(names(result), vec!["e", "d", "a", "g", "c", "b", "f"]);
+
+        // 无穷大放在一起，负无穷在前
+        let sorter = BucketSorter::new().nan(NanPolicy::Last);
+        let result = sorter.infinity(InfinityPolicy::First).sort(v.clone(), |e| e.0)?;
+        assert_eq!(names(result), vec!["e", "c", "d", "a", "g", "b", "f"]);
+        let result = sorter.infinity(InfinityPolicy::Last).sort(v.clone(), |e| e.0)?;
+        assert_eq!(names(result), vec!["d", "a", "g", "e", "c", "b", "f"]);
+
+        // 只有NaN和无穷大，没有有限的键
+        let v = vec![(f64::NAN, "a"), (f64::INFINITY, "b")];
+        let result = sorter.infinity(InfinityPolicy::Ends).sort(v, |e| e.0)?;
+        assert_eq!(names(result), vec!["b", "a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_arg_sort_non_finite_keys() -> Result<(), BucketSortError> {
+        let v = vec![2.0, f64::NAN, f64::INFINITY, -1.0, f64::NEG_INFINITY, 2.0];
+        let sorter = BucketSorter::new().nan(NanPolicy::Last).infinity(InfinityPolicy::Ends);
+        // 稳定，两个2.0保持原来的先后
+        assert_eq!(sorter.arg_sort(&v, |e| *e)?, vec![4, 3, 0, 5, 2, 1]);
+        assert_eq!(BucketSorter::new().arg_sort(&v, |e| *e), Err(BucketSortError::NotANumber));
+        Ok(())
+    }
+
+    #[test]
+    fn it_sort_with_every_storage() -> Result<(), BucketSortError> {
+        // 大部分键挤在很小的一段里
+        let key = |i: usize| {
+            if i.is_multiple_of(10) { (i as f64) * 1e3 } else { (((i * 37) % 101) as f64) * 1e-9 }
+        };
+        let v: Vec<(f64, usize)> = (0..2000).map(|i| (key(i), i)).collect();
+        let mut expected = v.clone();
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0)); // 稳定
+
+        let sorter = BucketSorter::new();
+        assert_eq!(sorter.sort(v.clone(), |e| e.0)?, expected);
+        let mapper = |e: &(f64, usize)| e.0;
+        type Items = Vec<(f64, (f64, usize))>;
+        type Insertion = VecBucket<(f64, usize), InsertionSorter<'static, Items>>;
+        let result = sorter.sort_with::<Insertion, _, _>(v.clone(), mapper)?;
+        assert_eq!(result, expected);
+        let result = sorter.sort_with::<VecBucket<_, MergeSorter<Items>>, _, _>(v.clone(), mapper)?;
+        assert_eq!(result, expected);
+        let result = sorter.sort_with::<RecursiveBucket<_>, _, _>(v, mapper)?;
+        assert_eq!(result, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+// 桶排序的诊断报告
+// 桶排序只有在键均匀分布时才是期望O(n)，键聚集在少数几个桶里时，桶内的插入排序退化成O(n^2)
+// 报告各个桶的占用情况和插入时的比较次数，用来判断该不该换成别的排序（比如RecursiveBucket或者归并排序）
+//
+// 卡方统计量：sum((O_i - E)^2 / E)，O_i是第i个桶的元素个数，E = n / 桶数是均匀时的期望
+// 键均匀分布时它近似服从自由度为(桶数 - 1)的卡方分布，均值是桶数 - 1，标准差是sqrt(2 * (桶数 - 1))
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketDiagnostics {
+    pub buckets: usize,
+    pub empty_buckets: usize,
+    pub max_occupancy: usize,
+    // 非空桶的平均元素个数
+    pub mean_occupancy: f64,
+    pub chi_squared: f64,
+    // 所有桶插入时比较键的总次数
+    pub comparisons: usize,
+}
+
+impl BucketDiagnostics {
+    // occupancy是每个桶的元素个数
+    pub fn new(occupancy: &[usize], comparisons: usize) -> BucketDiagnostics {
+        let buckets = occupancy.len();
+        let total: usize = occupancy.iter().sum();
+        let empty_buckets = occupancy
+            .iter()
+            .filter(|&&count| count == 0)
+            .count();
+        let max_occupancy = occupancy.iter().copied().max().unwrap_or(0);
+        let mean_occupancy = if empty_buckets < buckets {
+            (total as f64) / ((buckets - empty_buckets) as f64)
+        } else {
+            0.0
+        };
+        let chi_squared = if total > 0 {
+            let expected = (total as f64) / (buckets as f64);
+            occupancy
+                .iter()
+                .map(|&count| {
+                    let diff = (count as f64) - expected;
+                    (diff * diff) / expected
+                })
+                .sum()
+        } else {
+            0.0
+        };
+        BucketDiagnostics {
+            buckets,
+            empty_buckets,
+            max_occupancy,
+            mean_occupancy,
+            chi_squared,
+            comparisons,
+        }
+    }
+
+    // 卡方统计量没有超过均匀分布时的均值加3个标准差
+    // 为false时键的分布明显不均匀，桶排序很可能比预期慢
+    pub fn looks_uniform(&self) -> bool {
+        if self.buckets <= 1 {
+            return true;
+        }
+        let degrees = (self.buckets - 1) as f64;
+        self.chi_squared <= degrees + 3.0 * (2.0 * degrees).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ bucket_sort, bucket_sort_with_diagnostics };
+    use algorithms_prelude::random::XorShift64;
+
+    #[test]
+    fn it_summarize_occupancy() {
+        let diagnostics = BucketDiagnostics::new(&[0, 3, 1, 0, 0, 2], 4);
+        assert_eq!(diagnostics.buckets, 6);
+        assert_eq!(diagnostics.empty_buckets, 3);
+        assert_eq!(diagnostics.max_occupancy, 3);
+        assert_eq!(diagnostics.mean_occupancy, 2.0);
+        // E = 1，(1 + 4 + 0 + 1 + 1 + 1) / 1
+        assert_eq!(diagnostics.chi_squared, 8.0);
+        assert_eq!(diagnostics.comparisons, 4);
+
+        let empty = BucketDiagnostics::new(&[], 0);
+        assert_eq!(empty.mean_occupancy, 0.0);
+        assert_eq!(empty.chi_squared, 0.0);
+        assert!(empty.looks_uniform());
+    }
+
+    #[test]
+    fn it_report_uniform_and_skewed_keys() -> Result<(), &'static str> {
+        let mut random = XorShift64::new(47);
+        let uniform: Vec<f64> = (0..10000).map(|_| random.next_f64()).collect();
+        let (result, diagnostics) = bucket_sort_with_diagnostics(uniform.clone(), |e| *e)?;
+        assert_eq!(result, bucket_sort(uniform, |e| *e)?);
+        assert_eq!(diagnostics.buckets, 10000);
+        assert!(diagnostics.looks_uniform());
+        assert!(diagnostics.max_occupancy < 10);
+        assert!(diagnostics.comparisons < 20000);
+
+        // 取8次方以后键挤在0附近
+        let skewed: Vec<f64> = (0..10000).map(|_| random.next_f64().powi(8)).collect();
+        let (result, diagnostics) = bucket_sort_with_diagnostics(skewed, |e| *e)?;
+        assert!(result.windows(2).all(|w| w[0] <= w[1]));
+        assert!(!diagnostics.looks_uniform());
+        assert!(diagnostics.max_occupancy > 1000);
+        assert!(diagnostics.comparisons > 100000);
+
+        assert_eq!(bucket_sort_with_diagnostics(vec![0.5, 1.5], |e| *e), Err("元素值溢出"));
+        Ok(())
+    }
+}
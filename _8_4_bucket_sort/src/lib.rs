@@ -1,5 +1,16 @@
+mod bucket_sorter;
+mod diagnostics;
 mod linked_list_bucket;
-use linked_list_bucket::*;
+mod sorted_list;
+mod storage;
+mod unit_disc;
+
+pub use bucket_sorter::{ BucketSortError, BucketSorter, InfinityPolicy, NanPolicy };
+pub use diagnostics::BucketDiagnostics;
+pub use linked_list_bucket::{ Bucket, BucketArena };
+pub use sorted_list::{ SortedList, SortedListIntoIter, SortedListIter, SortedListIterMut };
+pub use storage::{ BucketStorage, RecursiveBucket, VecBucket };
+pub use unit_disc::unit_disc_sort;
 
 use conv::*;
 
@@ -9,34 +20,100 @@ use conv::*;
 // 输入元素乘以n并向下取整，结果会分布在[0,n)中
 // 找到对应的桶，桶是一个链表，元素按线性查找插入排序到桶中
 // 将所有桶首尾相接
+// 所有桶的节点都放在同一个BucketArena里，只分配一次
 pub fn bucket_sort<T, F>(arr: Vec<T>, mapper: F) -> Result<Vec<T>, &'static str>
     where F: Fn(&T) -> f64
+{
+    let buckets = fill_buckets(arr, mapper, |_| {})?;
+
+    // 按桶的顺序依次取出，并连接
+    Ok(buckets.into_iter().collect())
+}
+
+// 建桶并进桶，每放进一个元素调用一次on_insert(桶号)
+fn fill_buckets<T, F, I>(
+    arr: Vec<T>,
+    mapper: F,
+    mut on_insert: I
+) -> Result<BucketArena<T>, &'static str>
+    where F: Fn(&T) -> f64, I: FnMut(usize)
 {
     let arr_length = arr.len();
 
     // 建桶
-    let mut buckets: Vec<Bucket<T>> = Vec::with_capacity(arr_length);
-    buckets.resize_with(arr_length, || Bucket::new());
+    let mut buckets = BucketArena::with_capacity(arr_length, arr_length);
 
     // 进桶
+    distribute(arr, mapper, |bucket_id, key, e| {
+        buckets.insert(bucket_id, key, e);
+        on_insert(bucket_id);
+    })?;
+    Ok(buckets)
+}
+
+// 进桶，n个元素分到n个桶里，算出每个元素的桶号后交给insert(桶号, 键, 元素)
+// 链表桶和BucketStorage的桶都从这里进桶
+fn distribute<T, F, I>(arr: Vec<T>, mapper: F, mut insert: I) -> Result<(), &'static str>
+    where F: Fn(&T) -> f64, I: FnMut(usize, f64, T)
+{
+    let arr_length = arr.len();
     for e in arr {
         let key = mapper(&e);
-        if key >= 0.0 && key < 1.0 {
+        if (0.0..1.0).contains(&key) {
             let bucket_id = (key * (arr_length as f64)).approx_as::<usize>().unwrap();
-            buckets[bucket_id].insert(key, e);
+            insert(bucket_id, key, e);
         } else {
             return Err("元素值溢出");
         }
     }
+    Ok(())
+}
+
+// 和bucket_sort相同，同时返回各个桶的占用情况，用来判断键的分布是否均匀
+pub fn bucket_sort_with_diagnostics<T, F>(
+    arr: Vec<T>,
+    mapper: F
+) -> Result<(Vec<T>, BucketDiagnostics), &'static str>
+    where F: Fn(&T) -> f64
+{
+    let mut occupancy = vec![0; arr.len()];
+    let buckets = fill_buckets(arr, mapper, |bucket_id| {
+        occupancy[bucket_id] += 1;
+    })?;
+    let diagnostics = BucketDiagnostics::new(&occupancy, buckets.comparisons());
+    Ok((buckets.into_iter().collect(), diagnostics))
+}
+
+// 指定桶的实现，比如
+// | bucket_sort_with::<VecBucket<_, MergeSorter<Vec<_>>>, _, _>(v, |e| *e)
+pub fn bucket_sort_with<B, T, F>(arr: Vec<T>, mapper: F) -> Result<Vec<T>, &'static str>
+    where B: BucketStorage<T>, F: Fn(&T) -> f64
+{
+    let arr_length = arr.len();
+
+    // 建桶
+    let mut buckets: Vec<B> = Vec::with_capacity(arr_length);
+    buckets.resize_with(arr_length, B::new);
+
+    // 进桶
+    distribute(arr, mapper, |bucket_id, key, e| buckets[bucket_id].insert(key, e))?;
 
     // 排序每个桶，并连接
-    let result = buckets
-        .into_iter()
-        .flat_map(|bucket| bucket)
-        .collect();
+    let mut result = Vec::with_capacity(arr_length);
+    for bucket in buckets {
+        bucket.drain_sorted(&mut result);
+    }
     Ok(result)
 }
 
+// 间接排序，返回排序后每个位置上元素的原下标
+// 桶里插入时相等的键排在后面，所以是稳定的
+pub fn arg_bucket_sort<T, F>(slice: &[T], mapper: F) -> Result<Vec<usize>, &'static str>
+    where F: Fn(&T) -> f64
+{
+    bucket_sort((0..slice.len()).collect(), |&i| mapper(&slice[i]))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -184,4 +261,11 @@ mod test {
             panic!("测试失败，不应该返回错误");
         }
     }
+
+    #[test]
+    fn it_arg_sort_ascending() {
+        let v = vec![0.79, 0.13, 0.16, 0.13, 0.39];
+        assert_eq!(arg_bucket_sort(&v, |e| *e), Ok(vec![1, 3, 2, 4, 0]));
+        assert_eq!(arg_bucket_sort(&v, |e| *e * 2.0), Err("元素值溢出"));
+    }
 }
@@ -1,81 +1,173 @@
-pub struct Bucket<T> {
-    head: Link<T>,
+use std::iter::Map;
+
+use crate::{
+    sorted_list::{ ListArena, SortedList, SortedListIntoIter },
+    storage::BucketStorage,
+};
+
+// 所有的桶共用一块数组，每个桶是其中的一个链表，节点的分配和释放见ListArena
+// 每个链表按key升序，插入时顺序查找位置，相等的key插在后面，所以是稳定的
+pub struct BucketArena<T>(ListArena<f64, T>);
+
+impl<T> BucketArena<T> {
+    // lists个空链表，数组预留capacity个节点
+    pub fn with_capacity(lists: usize, capacity: usize) -> BucketArena<T> {
+        BucketArena(ListArena::with_capacity(|prev, next| prev <= next, lists, capacity))
+    }
+
+    // 插入第list个链表，key升序，顺序查询
+    pub fn insert(&mut self, list: usize, key: f64, elem: T) {
+        self.0.insert(list, key, elem);
+    }
+
+    // 到目前为止插入时比较键的次数，桶里元素多了会按平方增长
+    pub fn comparisons(&self) -> usize {
+        self.0.comparisons()
+    }
+
+    // 弹出第list个链表的第一个元素，节点回到自由表
+    pub fn pop_front(&mut self, list: usize) -> Option<T> {
+        self.0.pop_front(list).map(|(_, elem)| elem)
+    }
 }
 
-type Link<T> = Option<Box<Node<T>>>;
-struct Node<T> {
-    key: f64,
-    elem: T,
-    next: Link<T>,
+// 迭代器 只实现夺取所有权的，依次弹出每个链表的元素
+pub struct BucketArenaIter<T> {
+    arena: BucketArena<T>,
+    list: usize,
 }
 
+impl<T> IntoIterator for BucketArena<T> {
+    type Item = T;
+    type IntoIter = BucketArenaIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BucketArenaIter { arena: self, list: 0 }
+    }
+}
+
+impl<T> Iterator for BucketArenaIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.list < self.arena.0.lists() {
+            if let Some(elem) = self.arena.pop_front(self.list) {
+                return Some(elem);
+            }
+            self.list += 1;
+        }
+        None
+    }
+}
+
+// 单独的一个链表桶，用于BucketStorage，就是键为f64、按升序排列的SortedList
+// bucket_sort直接用一个BucketArena装下所有的桶，只有通过BucketStorage逐个建桶时才用它
+pub struct Bucket<T>(SortedList<f64, T>);
+
 impl<T> Bucket<T> {
     pub fn new() -> Bucket<T> {
-        Bucket { head: None }
+        Bucket(SortedList::new(|prev, next| prev <= next))
     }
 
     // key升序插入，顺序查询
     pub fn insert(&mut self, key: f64, elem: T) {
-        let mut new_node = Box::new(Node { key, elem, next: None });
-        match &mut self.head {
-            None => {
-                // 空桶
-                self.head = Some(new_node);
-            }
-            Some(head_node) => {
-                if head_node.key > new_node.key {
-                    //插入开头
-                    new_node.next = self.head.take();
-                    self.head = Some(new_node);
-                } else {
-                    // 顺序查询
-                    unsafe {
-                        let mut cur_node = head_node as *mut Box<Node<T>>;
-                        while let Some(next_node) = &mut (*cur_node).next {
-                            if next_node.key > new_node.key {
-                                break;
-                            } else {
-                                cur_node = next_node;
-                            }
-                        }
-                        new_node.next = (*cur_node).next.take();
-                        (*cur_node).next = Some(new_node);
-                    }
-                }
-            }
-        }
+        self.0.insert(key, elem);
     }
-}
 
-// 递归释放变循环释放
-impl<T> Drop for Bucket<T> {
-    fn drop(&mut self) {
-        let mut cur_link = self.head.take();
-        while let Some(mut box_node) = cur_link {
-            cur_link = box_node.next.take();
-        }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_list(&self) -> &SortedList<f64, T> {
+        &self.0
+    }
+
+    pub fn into_list(self) -> SortedList<f64, T> {
+        self.0
     }
 }
 
-pub struct BucketIter<T>(Bucket<T>);
+impl<T> Default for Bucket<T> {
+    fn default() -> Self {
+        Bucket::new()
+    }
+}
 
 impl<T> IntoIterator for Bucket<T> {
     type Item = T;
-    type IntoIter = BucketIter<T>;
+    type IntoIter = Map<SortedListIntoIter<f64, T>, fn((f64, T)) -> T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        BucketIter(self)
+        self.0.into_iter().map(|(_, elem)| elem)
     }
 }
 
-// 迭代器 只实现夺取所有权的
-impl<T> Iterator for BucketIter<T> {
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        // 弹出
-        self.0.head.take().map(|node| {
-            self.0.head = node.next;
-            node.elem
-        })
+impl<T> BucketStorage<T> for Bucket<T> {
+    fn new() -> Self {
+        Bucket::new()
+    }
+
+    fn insert(&mut self, key: f64, elem: T) {
+        Bucket::insert(self, key, elem);
+    }
+
+    fn drain_sorted(self, out: &mut Vec<T>) {
+        out.extend(self);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_keep_lists_sorted_and_stable() {
+        let mut arena = BucketArena::with_capacity(3, 8);
+        for (list, key, name) in [
+            (1, 0.5, "a"),
+            (0, 0.3, "b"),
+            (1, 0.2, "c"),
+            (1, 0.5, "d"),
+            (2, 0.9, "e"),
+            (1, 0.7, "f"),
+            (1, 0.5, "g"),
+        ] {
+            arena.insert(list, key, name);
+        }
+        // 按链表的顺序依次输出，每个链表内key升序，相等的保持插入顺序
+        let result: Vec<&str> = arena.into_iter().collect();
+        assert_eq!(result, vec!["b", "c", "a", "d", "g", "f", "e"]);
+    }
+
+    #[test]
+    fn it_reuse_freed_nodes() {
+        let mut arena = BucketArena::with_capacity(2, 4);
+        for i in 0..4 {
+            arena.insert(i % 2, i as f64, i);
+        }
+        assert_eq!(arena.pop_front(0), Some(0));
+        assert_eq!(arena.pop_front(0), Some(2));
+        assert_eq!(arena.pop_front(0), None);
+        // 释放的两个节点被重新分配，数组没有变长
+        arena.insert(1, 0.0, 4);
+        arena.insert(0, 9.0, 5);
+        assert_eq!(arena.0.allocated(), 4);
+        // 前四次各比较0、0、1、1次，再插入的0.0和1比较一次，9.0插进空链表
+        assert_eq!(arena.comparisons(), 3);
+        assert_eq!(arena.into_iter().collect::<Vec<_>>(), vec![5, 4, 1, 3]);
+    }
+
+    #[test]
+    fn it_drop_owned_elements() {
+        // 带堆内存的元素，没取完就丢弃，配合ASan检查泄漏
+        let mut bucket = Bucket::new();
+        for i in 0..100 {
+            bucket.insert(((i * 37) % 100) as f64, format!("{}", i));
+        }
+        let mut iter = bucket.into_iter();
+        assert_eq!(iter.next(), Some("0".to_string()));
     }
 }
@@ -0,0 +1,485 @@
+use std::marker::PhantomData;
+
+// 多个有序单链表共用一块数组（10.3节，对象的分配与释放）
+// 节点不再各自Box一次，而是放在一个预先分配好的数组nodes里，链接用下标表示
+// 被释放的节点用next串成一个自由表，再分配时先从自由表里取，用完了才往数组末尾追加
+// 键的顺序由compare决定，compare(prev, next)为真表示prev可以排在next前面，
+// 新元素插在最后一个满足compare(e, 新元素)的元素e之后，
+// 所以compare包含等于（<=）时，相等的键保持插入的先后，是稳定的
+// SortedList只用其中一个链表，BucketArena每个桶一个链表，键是f64
+pub(crate) struct ListArena<K, T> {
+    nodes: Vec<Node<K, T>>,
+    heads: Vec<Link>,
+    free: Link,
+    compare: fn(prev: &K, next: &K) -> bool,
+    comparisons: usize, // 插入时比较键的总次数
+}
+
+type Link = Option<usize>;
+
+struct Node<K, T> {
+    entry: Option<(K, T)>, // 在自由表中时为None
+    next: Link,
+}
+
+impl<K, T> ListArena<K, T> {
+    // lists个空链表，数组预留capacity个节点
+    pub(crate) fn with_capacity(
+        compare: fn(prev: &K, next: &K) -> bool,
+        lists: usize,
+        capacity: usize
+    ) -> ListArena<K, T> {
+        ListArena {
+            nodes: Vec::with_capacity(capacity),
+            heads: vec![None; lists],
+            free: None,
+            compare,
+            comparisons: 0,
+        }
+    }
+
+    pub(crate) fn lists(&self) -> usize {
+        self.heads.len()
+    }
+
+    pub(crate) fn comparisons(&self) -> usize {
+        self.comparisons
+    }
+
+    // 数组里的节点个数，包括自由表里的
+    #[cfg(test)]
+    pub(crate) fn allocated(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // ALLOCATE-OBJECT
+    fn allocate(&mut self, key: K, elem: T) -> usize {
+        let node = Node { entry: Some((key, elem)), next: None };
+        match self.free {
+            Some(x) => {
+                self.free = self.nodes[x].next;
+                self.nodes[x] = node;
+                x
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // FREE-OBJECT
+    fn free(&mut self, x: usize) -> (K, T) {
+        let entry = self.nodes[x].entry.take().unwrap();
+        self.nodes[x].next = self.free;
+        self.free = Some(x);
+        entry
+    }
+
+    fn key(&self, x: usize) -> &K {
+        &self.nodes[x].entry.as_ref().unwrap().0
+    }
+
+    // 插入第list个链表，顺序查找插入位置，O(n)
+    pub(crate) fn insert(&mut self, list: usize, key: K, elem: T) {
+        let compare = self.compare;
+        let mut prev = None;
+        let mut cur = self.heads[list];
+        while let Some(x) = cur {
+            self.comparisons += 1;
+            if !compare(self.key(x), &key) {
+                break;
+            }
+            prev = cur;
+            cur = self.nodes[x].next;
+        }
+        let x = self.allocate(key, elem);
+        self.link_after(list, prev, x);
+    }
+
+    // 把x接在prev后面，prev为None时接在第list个链表的开头
+    fn link_after(&mut self, list: usize, prev: Link, x: usize) {
+        match prev {
+            Some(p) => {
+                self.nodes[x].next = self.nodes[p].next;
+                self.nodes[p].next = Some(x);
+            }
+            None => {
+                self.nodes[x].next = self.heads[list];
+                self.heads[list] = Some(x);
+            }
+        }
+    }
+
+    // 摘下prev后面的节点（prev为None时摘下第list个链表的第一个），节点回到自由表
+    fn unlink_after(&mut self, list: usize, prev: Link) -> (K, T) {
+        let x = match prev {
+            Some(p) => self.nodes[p].next.unwrap(),
+            None => self.heads[list].unwrap(),
+        };
+        let next = self.nodes[x].next;
+        match prev {
+            Some(p) => {
+                self.nodes[p].next = next;
+            }
+            None => {
+                self.heads[list] = next;
+            }
+        }
+        self.free(x)
+    }
+
+    // 弹出第list个链表的第一个元素
+    pub(crate) fn pop_front(&mut self, list: usize) -> Option<(K, T)> {
+        self.heads[list]?;
+        Some(self.unlink_after(list, None))
+    }
+}
+
+// 有序单链表，只有一个链表的ListArena
+pub struct SortedList<K, T> {
+    arena: ListArena<K, T>,
+    len: usize,
+}
+
+// SortedList用的是arena的第0个链表
+const LIST: usize = 0;
+
+impl<K, T> SortedList<K, T> {
+    pub fn new(compare: fn(prev: &K, next: &K) -> bool) -> SortedList<K, T> {
+        SortedList::with_capacity(compare, 0)
+    }
+
+    pub fn with_capacity(
+        compare: fn(prev: &K, next: &K) -> bool,
+        capacity: usize
+    ) -> SortedList<K, T> {
+        SortedList { arena: ListArena::with_capacity(compare, 1, capacity), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn head(&self) -> Link {
+        self.arena.heads[LIST]
+    }
+
+    // 顺序查找插入位置，O(n)
+    pub fn insert(&mut self, key: K, elem: T) {
+        self.arena.insert(LIST, key, elem);
+        self.len += 1;
+    }
+
+    // 把新节点接在tail后面，用于按顺序逐个追加，返回新的tail
+    fn push_after(&mut self, tail: Link, key: K, elem: T) -> Link {
+        let x = self.arena.allocate(key, elem);
+        self.arena.link_after(LIST, tail, x);
+        self.len += 1;
+        Some(x)
+    }
+
+    fn unlink_after(&mut self, prev: Link) -> (K, T) {
+        self.len -= 1;
+        self.arena.unlink_after(LIST, prev)
+    }
+
+    pub fn peek(&self) -> Option<(&K, &T)> {
+        self.head().map(|x| {
+            let (key, elem) = self.arena.nodes[x].entry.as_ref().unwrap();
+            (key, elem)
+        })
+    }
+
+    pub fn pop_front(&mut self) -> Option<(K, T)> {
+        self.head()?;
+        Some(self.unlink_after(None))
+    }
+
+    // 删除第一个满足条件的元素
+    pub fn remove_first_matching<P>(&mut self, mut predicate: P) -> Option<(K, T)>
+        where P: FnMut(&K, &T) -> bool
+    {
+        let mut prev = None;
+        let mut cur = self.head();
+        while let Some(x) = cur {
+            let (key, elem) = self.arena.nodes[x].entry.as_ref().unwrap();
+            if predicate(key, elem) {
+                return Some(self.unlink_after(prev));
+            }
+            prev = cur;
+            cur = self.arena.nodes[x].next;
+        }
+        None
+    }
+
+    // 归并另一个有序链表，O(n + m)
+    // 按self的compare归并，other应当按同样的顺序排好；compare包含等于时，相等的键self的在前
+    pub fn merge(&mut self, other: SortedList<K, T>) {
+        self.len += other.len;
+        let arena = &mut self.arena;
+
+        // 先把other的节点按顺序搬进self的数组，串成链表b
+        let mut b_head = None;
+        let mut b_tail: Link = None;
+        for (key, elem) in other {
+            let x = arena.allocate(key, elem);
+            match b_tail {
+                Some(tail) => {
+                    arena.nodes[tail].next = Some(x);
+                }
+                None => {
+                    b_head = Some(x);
+                }
+            }
+            b_tail = Some(x);
+        }
+
+        // 和归并排序的merge一样，每次取两个链表头中应当在前的一个
+        let compare = arena.compare;
+        let mut a = arena.heads[LIST];
+        let mut b = b_head;
+        let mut tail: Link = None;
+        while let (Some(x), Some(y)) = (a, b) {
+            let take = if compare(arena.key(x), arena.key(y)) {
+                a = arena.nodes[x].next;
+                x
+            } else {
+                b = arena.nodes[y].next;
+                y
+            };
+            match tail {
+                Some(t) => {
+                    arena.nodes[t].next = Some(take);
+                }
+                None => {
+                    arena.heads[LIST] = Some(take);
+                }
+            }
+            tail = Some(take);
+        }
+        let rest = a.or(b);
+        match tail {
+            Some(t) => {
+                arena.nodes[t].next = rest;
+            }
+            None => {
+                arena.heads[LIST] = rest;
+            }
+        }
+    }
+
+    // 从第at个元素处断开，返回后半段，前半段留在self，at > len时panic
+    pub fn split_off(&mut self, at: usize) -> SortedList<K, T> {
+        assert!(at <= self.len, "split_off index (is {}) should be <= len (is {})", at, self.len);
+        let mut rest = SortedList::with_capacity(self.arena.compare, self.len - at);
+        let mut prev: Link = None;
+        for _ in 0..at {
+            prev = match prev {
+                Some(p) => self.arena.nodes[p].next,
+                None => self.head(),
+            };
+        }
+        // 后半段已经有序，逐个接到rest的末尾
+        let mut tail = None;
+        while self.len > at {
+            let (key, elem) = self.unlink_after(prev);
+            tail = rest.push_after(tail, key, elem);
+        }
+        rest
+    }
+
+    pub fn iter(&self) -> SortedListIter<'_, K, T> {
+        SortedListIter { list: self, cur: self.head() }
+    }
+
+    // 只能修改元素，不能修改键，否则顺序会被破坏
+    pub fn iter_mut(&mut self) -> SortedListIterMut<'_, K, T> {
+        let cur = self.head();
+        SortedListIterMut { nodes: self.arena.nodes.as_mut_ptr(), cur, marker: PhantomData }
+    }
+}
+
+pub struct SortedListIter<'a, K, T> {
+    list: &'a SortedList<K, T>,
+    cur: Link,
+}
+
+impl<'a, K, T> Iterator for SortedListIter<'a, K, T> {
+    type Item = (&'a K, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.cur?;
+        let node = &self.list.arena.nodes[x];
+        self.cur = node.next;
+        let (key, elem) = node.entry.as_ref().unwrap();
+        Some((key, elem))
+    }
+}
+
+// 沿着链表走，每个节点只经过一次，所以给出的可变引用互不重叠
+pub struct SortedListIterMut<'a, K, T> {
+    nodes: *mut Node<K, T>,
+    cur: Link,
+    marker: PhantomData<&'a mut Node<K, T>>,
+}
+
+impl<'a, K, T> Iterator for SortedListIterMut<'a, K, T> {
+    type Item = (&'a K, &'a mut T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.cur?;
+        let node = unsafe { &mut *self.nodes.add(x) };
+        self.cur = node.next;
+        let (key, elem) = node.entry.as_mut().unwrap();
+        Some((&*key, elem))
+    }
+}
+
+// 迭代器 夺取所有权的，依次弹出
+pub struct SortedListIntoIter<K, T>(SortedList<K, T>);
+
+impl<K, T> IntoIterator for SortedList<K, T> {
+    type Item = (K, T);
+    type IntoIter = SortedListIntoIter<K, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SortedListIntoIter(self)
+    }
+}
+
+impl<K, T> Iterator for SortedListIntoIter<K, T> {
+    type Item = (K, T);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<'a, K, T> IntoIterator for &'a SortedList<K, T> {
+    type Item = (&'a K, &'a T);
+    type IntoIter = SortedListIter<'a, K, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, T> Clone for SortedList<K, T> where K: Clone, T: Clone {
+    fn clone(&self) -> Self {
+        let mut list = SortedList::with_capacity(self.arena.compare, self.len);
+        let mut tail = None;
+        for (key, elem) in self {
+            tail = list.push_after(tail, key.clone(), elem.clone());
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries<K: Clone, T: Clone>(list: &SortedList<K, T>) -> Vec<(K, T)> {
+        list.iter()
+            .map(|(k, e)| (k.clone(), e.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn it_insert_and_pop_in_order() {
+        let mut list = SortedList::new(|prev: &i32, next: &i32| prev <= next);
+        assert!(list.is_empty());
+        assert_eq!(list.peek(), None);
+        for (key, name) in [(3, "a"), (1, "b"), (3, "c"), (-2, "d"), (1, "e")] {
+            list.insert(key, name);
+        }
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.peek(), Some((&-2, &"d")));
+        // 相等的键保持插入顺序
+        assert_eq!(entries(&list), vec![(-2, "d"), (1, "b"), (1, "e"), (3, "a"), (3, "c")]);
+        assert_eq!(list.pop_front(), Some((-2, "d")));
+        assert_eq!(list.len(), 4);
+
+        // 降序，不包含等于时相等的键后插入的在前
+        let mut desc = SortedList::new(|prev: &i32, next: &i32| prev > next);
+        for (key, name) in [(1, "a"), (2, "b"), (1, "c")] {
+            desc.insert(key, name);
+        }
+        assert_eq!(desc.into_iter().collect::<Vec<_>>(), vec![(2, "b"), (1, "c"), (1, "a")]);
+    }
+
+    #[test]
+    fn it_remove_and_reuse_nodes() {
+        let mut list = SortedList::new(|prev: &String, next: &String| prev <= next);
+        for word in ["pear", "apple", "fig", "kiwi"] {
+            list.insert(word.to_string(), word.len());
+        }
+        assert_eq!(list.remove_first_matching(|_, &len| len == 4), Some(("kiwi".to_string(), 4)));
+        assert_eq!(list.remove_first_matching(|key, _| key.starts_with('z')), None);
+        assert_eq!(list.len(), 3);
+        // 删掉的节点被重新使用
+        list.insert("banana".to_string(), 6);
+        assert_eq!(list.arena.allocated(), 4);
+
+        for (_, len) in list.iter_mut() {
+            *len *= 10;
+        }
+        let keys: Vec<&str> = list
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(keys, vec!["apple", "banana", "fig", "pear"]);
+        assert_eq!(list.peek(), Some((&"apple".to_string(), &50)));
+    }
+
+    #[test]
+    fn it_merge_sorted_lists() {
+        let compare = |prev: &u32, next: &u32| prev <= next;
+        let mut a = SortedList::new(compare);
+        let mut b = SortedList::new(compare);
+        for key in [1, 4, 4, 9] {
+            a.insert(key, 'a');
+        }
+        for key in [0, 4, 10, 11] {
+            b.insert(key, 'b');
+        }
+        a.merge(b);
+        assert_eq!(a.len(), 8);
+        // 相等的键a的在前
+        assert_eq!(
+            entries(&a),
+            vec![(0, 'b'), (1, 'a'), (4, 'a'), (4, 'a'), (4, 'b'), (9, 'a'), (10, 'b'), (11, 'b')]
+        );
+
+        a.merge(SortedList::new(compare));
+        assert_eq!(a.len(), 8);
+        let mut empty = SortedList::new(compare);
+        empty.merge(a.clone());
+        assert_eq!(entries(&empty), entries(&a));
+    }
+
+    #[test]
+    fn it_split_off() {
+        let mut list = SortedList::new(|prev: &usize, next: &usize| prev <= next);
+        for key in (0..10).rev() {
+            list.insert(key, key * key);
+        }
+        let rest = list.split_off(6);
+        assert_eq!(list.len(), 6);
+        assert_eq!(rest.len(), 4);
+        assert_eq!(entries(&list), (0..6).map(|k| (k, k * k)).collect::<Vec<_>>());
+        assert_eq!(entries(&rest), (6..10).map(|k| (k, k * k)).collect::<Vec<_>>());
+
+        // 拆开的两段还能再插入、再归并回去
+        let mut rest = rest;
+        rest.insert(3, 0);
+        list.merge(rest);
+        assert_eq!(list.len(), 11);
+        assert!(list.split_off(11).is_empty());
+        let all = list.split_off(0);
+        assert!(list.is_empty());
+        assert_eq!(all.iter().nth(4), Some((&3, &0)));
+    }
+}
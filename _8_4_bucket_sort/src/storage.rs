@@ -0,0 +1,125 @@
+use std::marker::PhantomData;
+
+use algorithms_prelude::{ CompareSorter, VecSorter };
+use issort::InsertionSorter;
+use merge_sort::MergeSorter;
+
+// 桶的实现
+// 链表桶（linked_list_bucket::Bucket）边插入边排序，每次插入都要顺序查找，
+// 输入不均匀、很多元素落进同一个桶时退化成O(n^2)
+// Vec桶只管追加，所有元素进桶以后再整体排序一次，用什么排序由类型参数决定
+// 递归桶把落进同一个桶的元素按它们自己的最小值和最大值再做一次桶排序，对聚集的输入更有效
+pub trait BucketStorage<T> {
+    fn new() -> Self;
+
+    fn insert(&mut self, key: f64, elem: T);
+
+    // 把桶里的元素按键排好序追加到out
+    fn drain_sorted(self, out: &mut Vec<T>);
+}
+
+// Vec桶最后用哪种排序由S决定，S可以是任何实现了VecSorter的Sorter，比如
+// | VecBucket<f64, MergeSorter<Vec<(f64, f64)>>>
+// 类型参数里只用到它的类型，生命周期随便写，实际排序时包装的是桶自己的Vec
+// 要保持桶排序的稳定性，应该选稳定的排序
+pub struct VecBucket<T, S> {
+    items: Vec<(f64, T)>,
+    sorter: PhantomData<S>,
+}
+
+impl<T, S> BucketStorage<T> for VecBucket<T, S>
+    where S: VecSorter<Element = (f64, T)>
+{
+    fn new() -> Self {
+        VecBucket { items: vec![], sorter: PhantomData }
+    }
+
+    fn insert(&mut self, key: f64, elem: T) {
+        self.items.push((key, elem));
+    }
+
+    fn drain_sorted(mut self, out: &mut Vec<T>) {
+        S::sort_vec(&mut self.items, |prev, next| prev.0 <= next.0);
+        out.extend(self.items.into_iter().map(|(_, elem)| elem));
+    }
+}
+
+pub struct RecursiveBucket<T> {
+    items: Vec<(f64, T)>,
+}
+
+// 桶里不超过这么多元素时直接插入排序
+const RECURSION_THRESHOLD: usize = 16;
+// 递归这么多层还没分开，说明键的分布极不均匀（比如按指数聚集），改用归并排序
+const MAX_RECURSION_DEPTH: usize = 32;
+
+impl<T> BucketStorage<T> for RecursiveBucket<T> {
+    fn new() -> Self {
+        RecursiveBucket { items: vec![] }
+    }
+
+    fn insert(&mut self, key: f64, elem: T) {
+        self.items.push((key, elem));
+    }
+
+    fn drain_sorted(self, out: &mut Vec<T>) {
+        recursive_bucket_sort(self.items, 0, out);
+    }
+}
+
+fn recursive_bucket_sort<T>(mut items: Vec<(f64, T)>, depth: usize, out: &mut Vec<T>) {
+    if items.len() <= RECURSION_THRESHOLD {
+        InsertionSorter(&mut items).sort_by(|prev, next| prev.0 <= next.0);
+        out.extend(items.into_iter().map(|(_, elem)| elem));
+        return;
+    }
+    if depth >= MAX_RECURSION_DEPTH {
+        MergeSorter(&mut items).sort_by(|prev, next| prev.0 <= next.0);
+        out.extend(items.into_iter().map(|(_, elem)| elem));
+        return;
+    }
+
+    let (lo, hi) = items
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(key, _)| (lo.min(key), hi.max(key)));
+    // 键全部相等，已经有序
+    let half_width = hi / 2.0 - lo / 2.0;
+    if half_width <= 0.0 {
+        out.extend(items.into_iter().map(|(_, elem)| elem));
+        return;
+    }
+
+    let bucket_count = items.len();
+    let mut buckets: Vec<Vec<(f64, T)>> = Vec::with_capacity(bucket_count);
+    buckets.resize_with(bucket_count, Vec::new);
+    for (key, elem) in items {
+        let ratio = (key / 2.0 - lo / 2.0) / half_width;
+        let bucket_id = ((ratio * (bucket_count as f64)) as usize).min(bucket_count - 1);
+        buckets[bucket_id].push((key, elem));
+    }
+    for bucket in buckets {
+        recursive_bucket_sort(bucket, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bucket_sort_with;
+    use quick_sort::IntroSorter;
+
+    #[test]
+    fn it_sort_exponentially_clustered_keys() {
+        // 键是2^-i，每一层递归只能从最大的那个桶里分出几个，要靠深度上限改用归并排序
+        let v: Vec<f64> = (1..=1000).rev().map(|i| (-(i as f64)).exp2()).collect();
+        let mut expected = v.clone();
+        expected.sort_by(f64::total_cmp);
+        let result = bucket_sort_with::<RecursiveBucket<_>, _, _>(v.clone(), |e| *e).unwrap();
+        assert_eq!(result, expected);
+        let result = bucket_sort_with::<VecBucket<_, MergeSorter<Vec<_>>>, _, _>(v.clone(), |e| *e);
+        assert_eq!(result.unwrap(), expected);
+        // 键各不相同，不稳定的排序也可以
+        let result = bucket_sort_with::<VecBucket<_, IntroSorter<Vec<_>>>, _, _>(v, |e| *e);
+        assert_eq!(result.unwrap(), expected);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{ bucket_sort_with, Bucket };
+
+// 单位圆内的点按到原点的距离排序（练习8.4-4）
+// 点在单位圆内均匀分布时，距离r并不均匀：半径r以内的概率是r^2，点更多落在靠外的地方
+// 所以桶不能按半径等分，而要按面积等分：第i个桶是半径在[√(i/n), √((i+1)/n))的圆环，
+// 每个圆环面积都是π/n，落进每个桶的期望点数相同，期望时间仍然是O(n)
+// r^2在[0, 1)上均匀分布，把r^2直接当作bucket_sort的键，floor(n * r^2)正好就是上面的圆环编号，
+// 而且r^2和r的顺序相同，不用开平方
+// 圆周上的点（r^2 == 1）不在[0, 1)内，单独放在最后；同样距离的点保持原来的先后
+pub fn unit_disc_sort<T, F>(points: Vec<T>, coordinates: F) -> Result<Vec<T>, &'static str>
+    where F: Fn(&T) -> (f64, f64)
+{
+    let squared_radius = |p: &T| {
+        let (x, y) = coordinates(p);
+        x * x + y * y
+    };
+
+    let mut inside = Vec::with_capacity(points.len());
+    let mut on_circle = vec![];
+    for p in points {
+        let r2 = squared_radius(&p);
+        if r2 < 1.0 {
+            inside.push(p);
+        } else if r2 == 1.0 {
+            on_circle.push(p);
+        } else {
+            // 包括NaN
+            return Err("点不在单位圆内");
+        }
+    }
+
+    let mut result = bucket_sort_with::<Bucket<T>, T, _>(inside, squared_radius)?;
+    result.extend(on_circle);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use algorithms_prelude::random::XorShift64;
+
+    // 在外接正方形里取点，落在圆外的丢掉重取
+    fn random_points(random: &mut XorShift64, n: usize) -> Vec<(f64, f64)> {
+        let mut points = Vec::with_capacity(n);
+        while points.len() < n {
+            let x = random.next_f64() * 2.0 - 1.0;
+            let y = random.next_f64() * 2.0 - 1.0;
+            if x * x + y * y < 1.0 {
+                points.push((x, y));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn it_sort_by_distance() {
+        let mut random = XorShift64::new(46);
+        let points = random_points(&mut random, 5000);
+        let squared_radius = |p: &(f64, f64)| p.0 * p.0 + p.1 * p.1;
+        let mut expected = points.clone();
+        expected.sort_by(|a, b| squared_radius(a).total_cmp(&squared_radius(b)));
+
+        let result = unit_disc_sort(points, |p| *p).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn it_keep_equal_distances_stable() {
+        let points = vec![
+            (1.0, 0.0, "a"),
+            (0.0, 0.5, "b"),
+            (0.0, 0.0, "c"),
+            (-0.5, 0.0, "d"),
+            (0.0, -1.0, "e"),
+            (0.3, 0.4, "f")
+        ];
+        let result = unit_disc_sort(points, |p| (p.0, p.1)).unwrap();
+        let names: Vec<&str> = result
+            .iter()
+            .map(|p| p.2)
+            .collect();
+        // 圆周上的a、e在最后
+        assert_eq!(names, vec!["c", "b", "d", "f", "a", "e"]);
+    }
+
+    #[test]
+    fn it_reject_points_outside() {
+        assert_eq!(unit_disc_sort(vec![(0.5, 0.5), (0.8, 0.8)], |p| *p), Err("点不在单位圆内"));
+        assert_eq!(unit_disc_sort(vec![(f64::NAN, 0.0)], |p| *p), Err("点不在单位圆内"));
+        assert_eq!(unit_disc_sort(vec![], |p: &(f64, f64)| *p), Ok(vec![]));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod permutation;
+pub mod random;
+pub mod stack;
+
 // 定义一个Sorter Trait
 // 建议实现Sorter Trait的是一个Wrapper
 // sort_by一个断言函数，定义的是前一个与后一个元素满足断言函数的关系。
@@ -13,6 +17,16 @@ pub trait CompareSorter {
     fn sort_by(&mut self, compare: fn(prev: &Self::Element, next: &Self::Element) -> bool);
 }
 
+// 按类型参数选择排序算法时用，比如桶排序的VecBucket<T, S>
+// Sorter是包装类型，生命周期是类型的一部分，泛型代码拿到类型S也没法用它包装自己的局部变量，
+// 所以由Sorter提供一个对任意生命周期的Vec排序的关联函数，类型参数里的生命周期写什么都行
+pub trait VecSorter: CompareSorter {
+    fn sort_vec(
+        vec: &mut Vec<Self::Element>,
+        compare: fn(prev: &Self::Element, next: &Self::Element) -> bool
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,17 +49,132 @@ mod tests {
                 let mut j = i;
                 while j > 0 && !compare(&vec[j - 1], &e) {
                     vec[j] = vec[j - 1];
-                    j = j - 1;
+                    j -= 1;
                 }
                 vec[j] = e;
             }
         }
     }
 
+    impl<'a, Elem: Copy> VecSorter for InsertionSorter<'a, Vec<Elem>> {
+        fn sort_vec(vec: &mut Vec<Elem>, compare: fn(prev: &Elem, next: &Elem) -> bool) {
+            InsertionSorter(vec).sort_by(compare);
+        }
+    }
+
     #[test]
     fn it_sort_ascending() {
         let mut v = vec![22, 43, 145, 1, 9];
         InsertionSorter(&mut v).sort_by(|prev, next| prev < next);
         assert_eq!(v, vec![1, 9, 22, 43, 145]);
     }
+
+    #[test]
+    fn it_sort_by_sorter_type() {
+        fn sort_both<S: VecSorter<Element = i32>>(a: &mut Vec<i32>, b: &mut Vec<i32>) {
+            S::sort_vec(a, |prev, next| prev < next);
+            S::sort_vec(b, |prev, next| prev > next);
+        }
+
+        let mut a = vec![3, 1, 2];
+        let mut b = vec![3, 1, 2];
+        sort_both::<InsertionSorter<Vec<i32>>>(&mut a, &mut b);
+        assert_eq!(a, vec![1, 2, 3]);
+        assert_eq!(b, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn it_random_is_reproducible() {
+        let mut a = random::XorShift64::new(42);
+        let mut b = random::XorShift64::new(42);
+        let mut c = random::XorShift64::new(43);
+        let xs: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+
+        for bound in 1..100 {
+            assert!(a.below(bound) < bound);
+        }
+        let x = a.next_f64();
+        assert!((0.0..1.0).contains(&x));
+
+        let mut v: Vec<usize> = (0..100).collect();
+        a.shuffle(&mut v);
+        assert_ne!(v, (0..100).collect::<Vec<_>>());
+        v.sort();
+        assert_eq!(v, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_runs_with_small_stack() {
+        let v = [3, 1, 2];
+        let sum = stack::run_with_stack(stack::SMALL_STACK, || v.iter().sum::<i32>());
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    fn it_arg_sort_and_apply_permutation() {
+        let names = vec!["d", "a", "c", "a", "b"];
+        let mut ages = vec![40, 10, 30, 11, 20];
+        let perm = permutation::arg_sort_with(
+            &names,
+            |prev, next| prev <= next,
+            |indexed, compare| InsertionSorter(indexed).sort_by(compare)
+        );
+        assert_eq!(perm, vec![1, 3, 4, 2, 0]);
+
+        // 同一个排列重排另一列
+        permutation::apply_permutation(&mut ages, &perm).unwrap();
+        assert_eq!(ages, vec![10, 11, 20, 30, 40]);
+
+        // 逆排列是名次，按名次放回就恢复原序
+        let ranks = permutation::invert_permutation(&perm).unwrap();
+        assert_eq!(ranks, vec![4, 0, 3, 1, 2]);
+        permutation::apply_permutation(&mut ages, &ranks).unwrap();
+        assert_eq!(ages, vec![40, 10, 30, 11, 20]);
+    }
+
+    #[test]
+    fn it_arg_sort_nested() {
+        // sort里再做一次间接排序，结束后外层的比较函数要恢复
+        let words = vec!["bb", "a", "ccc"];
+        let numbers = vec![3.0, 1.0, 2.0];
+        let mut inner = vec![];
+        let outer = permutation::arg_sort_with(
+            &words,
+            |prev, next| prev.len() >= next.len(),
+            |indexed, compare| {
+                inner = permutation::arg_sort_with(
+                    &numbers,
+                    |prev, next| prev <= next,
+                    |indexed, compare| InsertionSorter(indexed).sort_by(compare)
+                );
+                InsertionSorter(indexed).sort_by(compare);
+            }
+        );
+        assert_eq!(inner, vec![1, 2, 0]);
+        assert_eq!(outer, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn it_reject_invalid_permutation() {
+        let mut v = vec![1, 2, 3];
+        assert_eq!(
+            permutation::apply_permutation(&mut v, &[0, 1]),
+            Err("Permutation length mismatch")
+        );
+        assert_eq!(permutation::apply_permutation(&mut v, &[0, 1, 1]), Err("Not a permutation"));
+        assert_eq!(permutation::invert_permutation(&[0, 3, 1]), Err("Not a permutation"));
+        assert_eq!(v, vec![1, 2, 3]);
+
+        // 一个大的环和许多不动点
+        let mut v: Vec<usize> = (0..1000).collect();
+        let perm: Vec<usize> = (0..1000)
+            .map(|i| if i % 2 == 0 { (i + 2) % 1000 } else { i })
+            .collect();
+        permutation::apply_permutation(&mut v, &perm).unwrap();
+        assert_eq!(v, perm);
+    }
 }
@@ -0,0 +1,134 @@
+// 排列（permutation）
+// 间接排序（argsort）不移动元素，而是返回一个下标序列perm，
+// 排序后的第i个元素是原序列的第perm[i]个。
+// 按列存储的表可以用同一个perm重排每一列；元素很大时只排下标也能省去搬运的开销。
+
+use std::cell::Cell;
+
+// 按下标比较两个元素，由arg_sort_with在进入sort之前设置
+type IndexCompare<'a> = dyn Fn(usize, usize) -> bool + 'a;
+
+thread_local! {
+    // CompareSorter只接受不捕获环境的fn，Indexed::compare没法带上用户的比较函数，
+    // 所以arg_sort_with把它放在线程局部变量里，整个排序只设置一次
+    // 擦掉了元素类型，只按下标比较，即使拿别的序列的Indexed来比也只会比错或越界panic
+    static CURRENT: Cell<Option<&'static IndexCompare<'static>>> = const { Cell::new(None) };
+}
+
+// 离开arg_sort_with时（包括panic）恢复外层的比较函数，arg_sort_with可以嵌套
+struct Restore(Option<&'static IndexCompare<'static>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|c| c.set(self.0));
+    }
+}
+
+// 下标和它指向的元素
+pub struct Indexed<'a, T> {
+    pub index: usize,
+    pub elem: &'a T,
+}
+
+// 只有一个下标和一个指针，复制起来很便宜；derive会要求T: Copy，所以手写
+impl<'a, T> Clone for Indexed<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Indexed<'a, T> {}
+
+impl<'a, T> Indexed<'a, T> {
+    // 用当前arg_sort_with的比较函数比较，只能在它的sort里、在同一个线程上调用
+    pub fn compare(prev: &Self, next: &Self) -> bool {
+        let compare = CURRENT.with(|c| c.get()).expect("Indexed::compare只能在arg_sort_with里调用");
+        compare(prev.index, next.index)
+    }
+}
+
+// 用sort对下标排序，sort拿到的是Indexed序列和它的比较函数
+// 各个Sorter不再各自提供arg_sort_by，间接排序都通过它，在sort里构造需要的Sorter，比如
+// | arg_sort_with(&vec, compare, |indexed, compare| InsertionSorter(indexed).sort_by(compare))
+// 比较函数放在线程局部变量里，sort不能把比较交给别的线程
+pub fn arg_sort_with<'a, T, S>(
+    slice: &'a [T],
+    compare: fn(prev: &T, next: &T) -> bool,
+    sort: S
+) -> Vec<usize>
+    where S: FnOnce(&mut Vec<Indexed<'a, T>>, fn(&Indexed<'a, T>, &Indexed<'a, T>) -> bool)
+{
+    let by_index = move |i: usize, j: usize| compare(&slice[i], &slice[j]);
+    let by_index: &IndexCompare = &by_index;
+    // SAFETY: 只是把生命周期放宽成'static，restore后声明所以先析构，
+    // 离开这个函数之前（包括panic时）CURRENT就恢复成外层的值，不会留下悬垂的引用
+    let by_index: &'static IndexCompare<'static> = unsafe { std::mem::transmute(by_index) };
+    let _restore = Restore(CURRENT.with(|c| c.replace(Some(by_index))));
+
+    let mut indexed: Vec<Indexed<T>> = slice
+        .iter()
+        .enumerate()
+        .map(|(index, elem)| Indexed { index, elem })
+        .collect();
+    sort(&mut indexed, Indexed::compare);
+    indexed
+        .into_iter()
+        .map(|e| e.index)
+        .collect()
+}
+
+pub fn is_permutation(perm: &[usize]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    for &i in perm {
+        if i >= perm.len() || seen[i] {
+            return false;
+        }
+        seen[i] = true;
+    }
+    true
+}
+
+// 原地把slice重排成slice[perm[0]], slice[perm[1]], ...
+// 按环分解，每个环沿着环依次交换，一个长度为k的环需要k - 1次交换
+// 额外空间只有每个位置一个标记位
+pub fn apply_permutation<T>(slice: &mut [T], perm: &[usize]) -> Result<(), &'static str> {
+    if slice.len() != perm.len() {
+        return Err("Permutation length mismatch");
+    }
+    if !is_permutation(perm) {
+        return Err("Not a permutation");
+    }
+
+    let mut done = vec![false; perm.len()];
+    for start in 0..perm.len() {
+        if done[start] {
+            continue;
+        }
+        // 位置j要放原来第perm[j]个元素，把它换过来，j再沿着环走到perm[j]
+        // 走回起点时，j上放的正好是原来的第start个元素
+        let mut j = start;
+        loop {
+            done[j] = true;
+            let k = perm[j];
+            if k == start {
+                break;
+            }
+            slice.swap(j, k);
+            j = k;
+        }
+    }
+    Ok(())
+}
+
+// 逆排列，inverse[perm[i]] = i
+// 对argsort的结果求逆，得到的是每个元素排序后的名次
+pub fn invert_permutation(perm: &[usize]) -> Result<Vec<usize>, &'static str> {
+    if !is_permutation(perm) {
+        return Err("Not a permutation");
+    }
+    let mut inverse = vec![0; perm.len()];
+    for (i, &p) in perm.iter().enumerate() {
+        inverse[p] = i;
+    }
+    Ok(inverse)
+}
@@ -0,0 +1,44 @@
+// 伪随机数发生器，xorshift64*
+// 仓库不引入rand依赖，算法里需要随机（随机化主元、生成测试数据）时用这个
+// 相同的种子总是产生相同的序列，所以结果可以复现
+// 不能用于密码学
+pub struct XorShift64(u64);
+
+impl XorShift64 {
+    pub fn new(seed: u64) -> XorShift64 {
+        // 状态不能为0，种子先用splitmix64打散一次，相近的种子也能得到不相关的序列
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        XorShift64(if z == 0 { 0x9e37_79b9_7f4a_7c15 } else { z })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // [0, bound)中的一个数，bound不能为0
+    // 用乘法取高位代替取模，偏差可以忽略
+    pub fn below(&mut self, bound: usize) -> usize {
+        (((self.next_u64() as u128) * (bound as u128)) >> 64) as usize
+    }
+
+    // [0, 1)中的一个数
+    pub fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64) / ((1u64 << 53) as f64)
+    }
+
+    // Fisher-Yates洗牌
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.below(i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
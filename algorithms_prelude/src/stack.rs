@@ -0,0 +1,24 @@
+use std::thread;
+
+// 验证栈深度有界用的小栈
+// 主线程的栈一般有8MB，测试线程也有2MB，递归很深的实现在这样的栈上可能侥幸通过，
+// 换成很小的栈才能暴露问题。O(lgn)的栈深度即使n = 10^7也只有二十几层，64KB足够
+pub const SMALL_STACK: usize = 64 * 1024;
+
+// 在栈大小为stack_size的线程里运行f，f可以借用调用者的数据
+// f中的panic会原样传递出来，栈溢出则会直接中止整个进程
+pub fn run_with_stack<F, R>(stack_size: usize, f: F) -> R
+    where F: FnOnce() -> R + Send, R: Send
+{
+    thread::scope(|scope| {
+        let handle = thread::Builder
+            ::new()
+            .stack_size(stack_size)
+            .spawn_scoped(scope, f)
+            .unwrap();
+        match handle.join() {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    })
+}